@@ -0,0 +1,15 @@
+//! Timing shared by the benches, which include it with `mod common;`.
+
+use std::time::{Duration, Instant};
+
+/// Calls `run` until `min_time` passes, and returns how many times it did
+/// and how long it took.
+pub fn measure(min_time: Duration, mut run: impl FnMut()) -> (u32, Duration) {
+    let start = Instant::now();
+    let mut iterations = 0;
+    while start.elapsed() < min_time {
+        run();
+        iterations += 1;
+    }
+    (iterations, start.elapsed())
+}
@@ -0,0 +1,63 @@
+//! Time of parsing and running a large generated program, of many functions
+//! with deeply nested expressions.
+//!
+//! Run with `cargo bench --bench large_program`.
+
+mod common;
+
+use std::{fmt::Write, hint::black_box, io, process::ExitCode, time::Duration};
+
+use codecrafters_interpreter::lox_run;
+
+/// How many functions are generated.
+const FUNCTIONS: usize = 1000;
+
+/// How many operations each function's expression nests.
+const DEPTH: usize = 40;
+
+/// How many times every function is called.
+const CALLS: usize = 50;
+
+/// The program is run for at least this long.
+const MIN_TIME: Duration = Duration::from_secs(5);
+
+/// `f<i>(x)` computes `(3 * (2 - (1 + x)))` nested `DEPTH` times, and
+/// returns it or `x`, whichever is larger.
+fn generate() -> String {
+    let mut src = String::new();
+    for i in 0..FUNCTIONS {
+        let mut expr = "x".to_string();
+        for depth in 0..DEPTH {
+            let op = ["+", "-", "*"][depth % 3];
+            expr = format!("({} {op} {expr})", depth % 7 + 1);
+        }
+        writeln!(src, "fun f{i}(x) {{").unwrap();
+        writeln!(src, "    var y = {expr};").unwrap();
+        writeln!(src, "    if (y > x) return y;").unwrap();
+        writeln!(src, "    return x;").unwrap();
+        writeln!(src, "}}").unwrap();
+    }
+    writeln!(src, "var sum = 0;").unwrap();
+    writeln!(src, "for (var i = 0; i < {CALLS}; i = i + 1) {{").unwrap();
+    for i in 0..FUNCTIONS {
+        writeln!(src, "    sum = sum + f{i}(i);").unwrap();
+    }
+    writeln!(src, "}}").unwrap();
+    writeln!(src, "print sum > 0;").unwrap();
+    src
+}
+
+fn main() {
+    let src = generate();
+    let (iterations, elapsed) = common::measure(MIN_TIME, || {
+        let exit_code = lox_run(black_box(&src), &mut io::sink(), &mut io::sink());
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+    });
+    println!(
+        "{:<10} {:>10.3} ms/iter {:>8} iters ({} KiB)",
+        "large",
+        elapsed.as_secs_f64() * 1000.0 / f64::from(iterations),
+        iterations,
+        src.len() / 1024
+    );
+}
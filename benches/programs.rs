@@ -0,0 +1,137 @@
+//! Time per run of standard Lox workloads, from source to the last
+//! statement, to track regressions of the interpreter.
+//!
+//! Run with `cargo bench --bench programs`, or `cargo bench --bench programs
+//! -- <name>...` for some of them.
+
+mod common;
+
+use std::{env, hint::black_box, io, process::ExitCode, time::Duration};
+
+use codecrafters_interpreter::lox_run;
+
+/// Calls a function recursively, which binds its argument and two locals.
+const FIB: &str = "fun fib(n) {
+    if (n < 2) return n;
+    var a = fib(n - 1);
+    var b = fib(n - 2);
+    return a + b;
+}
+print fib(25);";
+
+/// Builds complete trees of maps, walks them and lets them go.
+const BINARY_TREES: &str = r#"fun tree(depth) {
+    var node = map();
+    if (depth > 0) {
+        set(node, "left", tree(depth - 1));
+        set(node, "right", tree(depth - 1));
+    }
+    return node;
+}
+fun check(node) {
+    var left = get(node, "left");
+    if (left == nil) return 1;
+    return 1 + check(left) + check(get(node, "right"));
+}
+var long_lived = tree(10);
+var total = 0;
+for (var depth = 4; depth <= 10; depth = depth + 2) {
+    for (var i = 0; i < 8; i = i + 1) total = total + check(tree(depth));
+}
+print total + check(long_lived);"#;
+
+/// Builds strings of short pieces, and longer ones of those.
+const STRING_CONCAT: &str = r#"var total = 0;
+for (var i = 0; i < 200; i = i + 1) {
+    var s = "";
+    for (var j = 0; j < 100; j = j + 1) s = s + "ab";
+    var line = "[" + s + "]";
+    total = total + len(line);
+}
+print total;"#;
+
+/// Reads a long string from variables and passes it to functions.
+const STRING_PASS: &str = r#"var text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat.";
+fun same(a, b) {
+    return a == b;
+}
+var count = 0;
+for (var i = 0; i < 20000; i = i + 1) {
+    if (same(text, text)) count = count + 1;
+}
+print count;"#;
+
+/// Stores and reads values of a map by string keys.
+const STRING_KEYS: &str = r#"var m = map();
+for (var i = 0; i < 10000; i = i + 1) {
+    set(m, "first", i);
+    set(m, "second", get(m, "first"));
+}
+print get(m, "second");"#;
+
+/// Makes closures and calls them, which read and assign captured variables.
+const CLOSURES: &str = "fun counter(step) {
+    var n = 0;
+    fun next() {
+        n = n + step;
+        return n;
+    }
+    return next;
+}
+fun compose(f, g) {
+    fun both() {
+        return f() + g();
+    }
+    return both;
+}
+var sum = 0;
+for (var i = 0; i < 2000; i = i + 1) {
+    var f = compose(counter(1), counter(i));
+    for (var j = 0; j < 10; j = j + 1) sum = sum + f();
+}
+print sum;";
+
+/// Loops within loops, of arithmetic on locals and no calls.
+const NESTED_LOOPS: &str = "var sum = 0;
+for (var i = 0; i < 100; i = i + 1) {
+    for (var j = 0; j < 100; j = j + 1) {
+        var k = 0;
+        while (k < 10) {
+            sum = sum + i * j - k;
+            k = k + 1;
+        }
+    }
+}
+print sum;";
+
+const PROGRAMS: [(&str, &str); 7] = [
+    ("fib", FIB),
+    ("binary_trees", BINARY_TREES),
+    ("string_concat", STRING_CONCAT),
+    ("string_pass", STRING_PASS),
+    ("string_keys", STRING_KEYS),
+    ("closures", CLOSURES),
+    ("nested_loops", NESTED_LOOPS),
+];
+
+/// Each program is run for at least this long.
+const MIN_TIME: Duration = Duration::from_secs(2);
+
+fn main() {
+    // Cargo passes `--bench` to benches without a harness.
+    let names: Vec<_> = env::args().skip(1).filter(|arg| arg != "--bench").collect();
+    for (name, src) in PROGRAMS {
+        if !names.is_empty() && !names.iter().any(|n| n == name) {
+            continue;
+        }
+        let (iterations, elapsed) = common::measure(MIN_TIME, || {
+            let exit_code = lox_run(black_box(src), &mut io::sink(), &mut io::sink());
+            assert_eq!(exit_code, ExitCode::SUCCESS);
+        });
+        println!(
+            "{name:<14} {:>10.3} ms/iter {:>8} iters",
+            elapsed.as_secs_f64() * 1000.0 / f64::from(iterations),
+            iterations,
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Throughput of `tokenize` on sources of different shapes.
+//!
+//! Run with `cargo bench --bench tokenize`.
+
+mod common;
+
+use std::{hint::black_box, io, time::Duration};
+
+use codecrafters_interpreter::lox_tokenize;
+
+/// Each source is tokenized for at least this long.
+const MIN_TIME: Duration = Duration::from_secs(1);
+
+fn main() {
+    let program = include_str!("../tests/test_suites/recursive.lox").repeat(2_000);
+    let comments = "// A comment line, which is skipped as a whole.\n".repeat(20_000);
+    let strings = "print \"a string literal with some words in it\";\n".repeat(20_000);
+    let numbers = "print 12345.6789 + 42 * 3.5;\n".repeat(20_000);
+
+    for (name, src) in [
+        ("program", &program),
+        ("comments", &comments),
+        ("strings", &strings),
+        ("numbers", &numbers),
+    ] {
+        let (iterations, elapsed) = common::measure(MIN_TIME, || {
+            lox_tokenize(black_box(src), &mut io::sink(), &mut io::sink());
+        });
+        let per_iteration = elapsed / iterations;
+        let throughput = src.len() as f64 * f64::from(iterations) / elapsed.as_secs_f64();
+        println!(
+            "{name:<10} {:>10.3} ms/iter {:>10.1} MiB/s",
+            per_iteration.as_secs_f64() * 1000.0,
+            throughput / (1024.0 * 1024.0),
+        );
+    }
+}
@@ -0,0 +1,647 @@
+//! Programs compiled by 'compile', which 'run' runs without tokenizing,
+//! parsing and resolving the source again.
+//!
+//! The file starts with [`MAGIC`], the [`FORMAT_VERSION`] and a checksum of
+//! the rest, which is: the path of the source, the source itself, the names
+//! of the globals in the order of their symbols, and the resolved
+//! statements. Tokens are kept as ranges of the source, so that the
+//! program borrows the file as it would borrow the source.
+//!
+//! Integers are little endian, and every length and line is 8 bytes.
+
+use std::{fs, rc::Rc};
+
+use crate::{
+    env::Binding,
+    expr::{
+        Assign, Binary, BinaryOp, ExprArena, ExprAst, FieldCall, FunctionCall, Grouping,
+        LiteralExpr, Unary, UnaryOp, Variable,
+    },
+    interner::{Interner, Symbol},
+    literal::{Literal, Number},
+    statement::{Block, Expression, For, FunctionDef, If, Print, Return, StmtAst, VarDecl, While},
+    token::{Token, TokenType},
+};
+
+const MAGIC: &[u8; 4] = b"LOXC";
+
+/// Changed whenever the layout, or the meaning of the tree, changes.
+const FORMAT_VERSION: u32 = 1;
+
+/// Magic, version and checksum.
+const HEADER_LEN: usize = 16;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub(crate) enum CacheError {
+    #[error("Error: Compiled by format version {0}, but this is version {FORMAT_VERSION}. Compile it again.")]
+    Version(u32),
+
+    #[error("Error: Checksum does not match. The compiled program is corrupted.")]
+    Checksum,
+
+    #[error("Error: '{0}' changed since it was compiled. Compile it again.")]
+    Stale(String),
+
+    #[error("Error: Malformed compiled program.")]
+    Malformed,
+
+    #[error("Error: A token is not in the source, and cannot be compiled.")]
+    OutsideSource,
+}
+
+/// A program read back from a compiled file.
+pub(crate) struct Program<'src> {
+    /// Names of the globals, to be interned in this order before running,
+    /// so that they get the symbols they were resolved to.
+    pub(crate) globals: Vec<&'src str>,
+    pub(crate) stmts: Vec<StmtAst<'src>>,
+}
+
+pub(crate) fn is_compiled(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// `path` is where the source was read from, or empty if not from a file.
+/// The statements are resolved with `interner`, and their tokens are ranges
+/// of `src`.
+pub(crate) fn encode(
+    src: &str,
+    path: &str,
+    stmts: &[StmtAst],
+    interner: &Interner,
+) -> Result<Vec<u8>, CacheError> {
+    let mut writer = Writer {
+        src,
+        bytes: Vec::new(),
+    };
+    writer.str(path);
+    writer.str(src);
+    let globals: Vec<_> = interner.strings().collect();
+    writer.len(globals.len());
+    for global in globals {
+        writer.str(global);
+    }
+    writer.stmts(stmts)?;
+
+    let payload = writer.bytes;
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    Ok(bytes)
+}
+
+/// Fails if the file is of another version, is corrupted, or if its source
+/// has changed since. A source which is gone does not make it stale.
+pub(crate) fn decode<'src>(
+    bytes: &'src [u8],
+    arena: &'src ExprArena<'src>,
+) -> Result<Program<'src>, CacheError> {
+    let header = bytes.get(..HEADER_LEN).ok_or(CacheError::Malformed)?;
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != FORMAT_VERSION {
+        return Err(CacheError::Version(version));
+    }
+    let payload = &bytes[HEADER_LEN..];
+    if u64::from_le_bytes(header[8..16].try_into().unwrap()) != checksum(payload) {
+        return Err(CacheError::Checksum);
+    }
+
+    let mut reader = Reader {
+        bytes: payload,
+        src: "",
+        arena,
+        globals: 0,
+        scopes: Vec::new(),
+    };
+    let path = reader.str()?;
+    reader.src = reader.str()?;
+    if !path.is_empty() && fs::read_to_string(path).is_ok_and(|current| current != reader.src) {
+        return Err(CacheError::Stale(path.to_string()));
+    }
+    let globals = reader.vec(Reader::str)?;
+    reader.globals = globals.len();
+    let stmts = reader.vec(Reader::stmt)?;
+    match reader.bytes.is_empty() {
+        true => Ok(Program { globals, stmts }),
+        false => Err(CacheError::Malformed),
+    }
+}
+
+/// FNV-1a.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Tags of the variants, by their index.
+const BINARY_OPS: [BinaryOp; 12] = [
+    BinaryOp::Plus,
+    BinaryOp::Minus,
+    BinaryOp::Star,
+    BinaryOp::Slash,
+    BinaryOp::EqualEqual,
+    BinaryOp::BangEqual,
+    BinaryOp::Greater,
+    BinaryOp::GreaterEqual,
+    BinaryOp::Less,
+    BinaryOp::LessEqual,
+    BinaryOp::And,
+    BinaryOp::Or,
+];
+
+const UNARY_OPS: [UnaryOp; 2] = [UnaryOp::Bang, UnaryOp::Minus];
+
+/// Only those a tree keeps a token of.
+const TOKEN_TYPES: [TokenType; 6] = [
+    TokenType::Identifier,
+    TokenType::String,
+    TokenType::Number,
+    TokenType::Nil,
+    TokenType::True,
+    TokenType::False,
+];
+
+struct Writer<'a> {
+    /// Which the tokens are ranges of.
+    src: &'a str,
+    bytes: Vec<u8>,
+}
+
+impl Writer<'_> {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn len(&mut self, value: usize) {
+        self.bytes.extend_from_slice(&(value as u64).to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    fn tag<T: PartialEq>(&mut self, tags: &[T], value: &T) {
+        let tag = tags.iter().position(|tag| tag == value).unwrap();
+        self.u8(tag as u8);
+    }
+
+    /// Fails if the token is not a range of the source.
+    fn token(&mut self, token: &Token) -> Result<(), CacheError> {
+        self.tag(&TOKEN_TYPES, &token.token_type);
+        let start = (token.src.as_ptr() as usize)
+            .checked_sub(self.src.as_ptr() as usize)
+            .filter(|start| self.src.get(*start..*start + token.src.len()) == Some(token.src))
+            .ok_or(CacheError::OutsideSource)?;
+        self.len(start);
+        self.len(token.src.len());
+        self.len(token.line);
+        self.len(token.column);
+        Ok(())
+    }
+
+    fn binding(&mut self, binding: Binding) {
+        match binding {
+            Binding::Unresolved => self.u8(0),
+            Binding::Global(symbol) => {
+                self.u8(1);
+                self.len(symbol.0);
+            }
+            Binding::Local { depth, slot } => {
+                self.u8(2);
+                self.len(depth);
+                self.len(slot);
+            }
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Nil => self.u8(0),
+            Literal::Boolean(b) => self.u8(1 + u8::from(*b)),
+            Literal::Number(n) => {
+                self.u8(3);
+                self.bytes.extend_from_slice(&n.0.to_le_bytes());
+            }
+            Literal::String(s) => {
+                self.u8(4);
+                self.str(s);
+            }
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[StmtAst]) -> Result<(), CacheError> {
+        self.len(stmts.len());
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &StmtAst) -> Result<(), CacheError> {
+        match stmt {
+            StmtAst::Expression(v) => {
+                self.u8(0);
+                self.expr(&v.expr)?;
+            }
+            StmtAst::Print(v) => {
+                self.u8(1);
+                self.expr(&v.expr)?;
+                self.len(v.start_line);
+            }
+            StmtAst::VarDecl(v) => {
+                self.u8(2);
+                self.expr(&v.var)?;
+                self.optional_expr(v.value.as_ref())?;
+                self.len(v.start_line);
+            }
+            StmtAst::Block(v) => {
+                self.u8(3);
+                self.stmts(&v.inner)?;
+                self.len(v.start_line);
+                self.len(v.line);
+            }
+            StmtAst::If(v) => {
+                self.u8(4);
+                self.expr(&v.condition)?;
+                self.stmt(&v.body)?;
+                self.optional_stmt(v.else_body.as_deref())?;
+                self.len(v.start_line);
+            }
+            StmtAst::While(v) => {
+                self.u8(5);
+                self.expr(&v.condition)?;
+                self.stmt(&v.body)?;
+                self.len(v.start_line);
+            }
+            StmtAst::For(v) => {
+                self.u8(6);
+                self.optional_stmt(v.initializer.as_deref())?;
+                self.optional_expr(v.condition.as_ref())?;
+                self.optional_expr(v.increment.as_ref())?;
+                self.stmt(&v.body)?;
+                self.len(v.start_line);
+            }
+            StmtAst::FunctionDef(v) => {
+                self.u8(7);
+                self.token(&v.name)?;
+                self.binding(v.binding);
+                self.len(v.arguments.len());
+                for argument in &v.arguments {
+                    self.token(argument)?;
+                }
+                self.stmts(&v.body)?;
+                self.len(v.start_line);
+                self.len(v.line);
+            }
+            StmtAst::Return(v) => {
+                self.u8(8);
+                self.optional_expr(v.expr.as_ref())?;
+                self.len(v.line);
+            }
+        }
+        Ok(())
+    }
+
+    fn optional_stmt(&mut self, stmt: Option<&StmtAst>) -> Result<(), CacheError> {
+        match stmt {
+            Some(stmt) => {
+                self.u8(1);
+                self.stmt(stmt)?;
+            }
+            None => self.u8(0),
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &ExprAst) -> Result<(), CacheError> {
+        match expr {
+            ExprAst::Assign(v) => {
+                self.u8(0);
+                self.expr(v.assignee)?;
+                self.expr(v.value)?;
+            }
+            ExprAst::Binary(v) => {
+                self.u8(1);
+                self.expr(v.left)?;
+                self.tag(&BINARY_OPS, &v.op);
+                self.expr(v.right)?;
+            }
+            ExprAst::FieldCall(v) => {
+                self.u8(2);
+                self.expr(v.object)?;
+                self.str(&v.field);
+            }
+            ExprAst::FunctionCall(v) => {
+                self.u8(3);
+                self.expr(v.callee)?;
+                self.len(v.arguments.len());
+                for argument in &v.arguments {
+                    self.expr(argument)?;
+                }
+            }
+            ExprAst::Grouping(v) => {
+                self.u8(4);
+                self.expr(v.inner)?;
+            }
+            ExprAst::LiteralExpr(v) => {
+                self.u8(5);
+                self.token(&v.token)?;
+                self.literal(&v.value);
+            }
+            ExprAst::Unary(v) => {
+                self.u8(6);
+                self.tag(&UNARY_OPS, &v.op);
+                self.expr(v.right)?;
+            }
+            ExprAst::Variable(v) => {
+                self.u8(7);
+                self.token(&v.var)?;
+                self.binding(v.binding.get());
+            }
+        }
+        Ok(())
+    }
+
+    fn optional_expr(&mut self, expr: Option<&ExprAst>) -> Result<(), CacheError> {
+        match expr {
+            Some(expr) => {
+                self.u8(1);
+                self.expr(expr)?;
+            }
+            None => self.u8(0),
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'src> {
+    /// What is left to read.
+    bytes: &'src [u8],
+    src: &'src str,
+    arena: &'src ExprArena<'src>,
+    /// How many globals there are, which symbols are below.
+    globals: usize,
+    /// How many locals are declared so far in each scope, as the
+    /// [`Resolver`](crate::resolver::Resolver) counts them. Slots are below.
+    scopes: Vec<usize>,
+}
+
+impl<'src> Reader<'src> {
+    fn take(&mut self, len: usize) -> Result<&'src [u8], CacheError> {
+        if len > self.bytes.len() {
+            return Err(CacheError::Malformed);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, CacheError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, CacheError> {
+        let bytes = self.take(8)?.try_into().unwrap();
+        usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| CacheError::Malformed)
+    }
+
+    fn str(&mut self) -> Result<&'src str, CacheError> {
+        let len = self.len()?;
+        std::str::from_utf8(self.take(len)?).map_err(|_| CacheError::Malformed)
+    }
+
+    fn vec<T>(
+        &mut self,
+        item: fn(&mut Self) -> Result<T, CacheError>,
+    ) -> Result<Vec<T>, CacheError> {
+        let len = self.len()?;
+        // Not reserved by `len`, which may be anything if malformed.
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    fn tag<T: Copy>(&mut self, tags: &[T]) -> Result<T, CacheError> {
+        let tag = self.u8()?;
+        tags.get(usize::from(tag))
+            .copied()
+            .ok_or(CacheError::Malformed)
+    }
+
+    fn optional<T>(
+        &mut self,
+        item: fn(&mut Self) -> Result<T, CacheError>,
+    ) -> Result<Option<T>, CacheError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => item(self).map(Some),
+            _ => Err(CacheError::Malformed),
+        }
+    }
+
+    fn token(&mut self) -> Result<Token<'src>, CacheError> {
+        let token_type = self.tag(&TOKEN_TYPES)?;
+        let (start, len) = (self.len()?, self.len()?);
+        let src = start
+            .checked_add(len)
+            .and_then(|end| self.src.get(start..end))
+            .ok_or(CacheError::Malformed)?;
+        Ok(Token {
+            line: self.len()?,
+            column: self.len()?,
+            src,
+            token_type,
+        })
+    }
+
+    /// Checked against the globals and the scopes, as an environment grows
+    /// to any slot it is given.
+    fn binding(&mut self) -> Result<Binding, CacheError> {
+        let binding = match self.u8()? {
+            0 => Binding::Unresolved,
+            1 => Binding::Global(Symbol(self.len()?)),
+            2 => Binding::Local {
+                depth: self.len()?,
+                slot: self.len()?,
+            },
+            _ => return Err(CacheError::Malformed),
+        };
+        let valid = match binding {
+            Binding::Unresolved => true,
+            Binding::Global(symbol) => symbol.0 < self.globals,
+            Binding::Local { depth, slot } => self
+                .scopes
+                .iter()
+                .rev()
+                .nth(depth)
+                .is_some_and(|&locals| slot < locals),
+        };
+        match valid {
+            true => Ok(binding),
+            false => Err(CacheError::Malformed),
+        }
+    }
+
+    /// A declaration takes the next slot of the innermost scope, if any.
+    fn declare(&mut self) {
+        if let Some(locals) = self.scopes.last_mut() {
+            *locals += 1;
+        }
+    }
+
+    /// Statements in a new scope, which starts with `locals` declared.
+    fn scope(&mut self, locals: usize) -> Result<Vec<StmtAst<'src>>, CacheError> {
+        self.scopes.push(locals);
+        let stmts = self.vec(Self::stmt);
+        self.scopes.pop();
+        stmts
+    }
+
+    fn literal(&mut self) -> Result<Literal, CacheError> {
+        match self.u8()? {
+            0 => Ok(Literal::Nil),
+            1 => Ok(Literal::Boolean(false)),
+            2 => Ok(Literal::Boolean(true)),
+            3 => {
+                let bytes = self.take(8)?.try_into().unwrap();
+                Ok(Literal::Number(Number(f64::from_le_bytes(bytes))))
+            }
+            4 => {
+                let s = self.str()?;
+                Ok(self.arena.string(s).into())
+            }
+            _ => Err(CacheError::Malformed),
+        }
+    }
+
+    fn boxed_stmt(&mut self) -> Result<Box<StmtAst<'src>>, CacheError> {
+        self.stmt().map(Box::new)
+    }
+
+    fn stmt(&mut self) -> Result<StmtAst<'src>, CacheError> {
+        let stmt = match self.u8()? {
+            0 => Expression { expr: self.expr()? }.into(),
+            1 => Print {
+                expr: self.expr()?,
+                start_line: self.len()?,
+            }
+            .into(),
+            2 => {
+                self.declare();
+                VarDecl {
+                    var: self.expr()?,
+                    value: self.optional(Self::expr)?,
+                    start_line: self.len()?,
+                }
+                .into()
+            }
+            3 => Block {
+                inner: self.scope(0)?,
+                start_line: self.len()?,
+                line: self.len()?,
+            }
+            .into(),
+            4 => If {
+                condition: self.expr()?,
+                body: self.boxed_stmt()?,
+                else_body: self.optional(Self::boxed_stmt)?,
+                start_line: self.len()?,
+            }
+            .into(),
+            5 => While {
+                condition: self.expr()?,
+                body: self.boxed_stmt()?,
+                start_line: self.len()?,
+            }
+            .into(),
+            6 => For {
+                initializer: self.optional(Self::boxed_stmt)?,
+                condition: self.optional(Self::expr)?,
+                increment: self.optional(Self::expr)?,
+                body: self.boxed_stmt()?,
+                start_line: self.len()?,
+            }
+            .into(),
+            7 => {
+                let name = self.token()?;
+                self.declare();
+                let binding = self.binding()?;
+                let arguments = self.vec(Self::token)?;
+                StmtAst::FunctionDef(Rc::new(FunctionDef {
+                    name,
+                    binding,
+                    body: self.scope(arguments.len())?,
+                    arguments,
+                    start_line: self.len()?,
+                    line: self.len()?,
+                }))
+            }
+            8 => Return {
+                expr: self.optional(Self::expr)?,
+                line: self.len()?,
+            }
+            .into(),
+            _ => return Err(CacheError::Malformed),
+        };
+        Ok(stmt)
+    }
+
+    fn expr(&mut self) -> Result<ExprAst<'src>, CacheError> {
+        let expr = match self.u8()? {
+            0 => Assign {
+                assignee: self.alloc()?,
+                value: self.alloc()?,
+            }
+            .into(),
+            1 => Binary {
+                left: self.alloc()?,
+                op: self.tag(&BINARY_OPS)?,
+                right: self.alloc()?,
+            }
+            .into(),
+            2 => FieldCall {
+                object: self.alloc()?,
+                field: self.str()?.to_string(),
+            }
+            .into(),
+            3 => FunctionCall {
+                callee: self.alloc()?,
+                arguments: self.vec(Self::expr)?,
+            }
+            .into(),
+            4 => Grouping {
+                inner: self.alloc()?,
+            }
+            .into(),
+            5 => LiteralExpr {
+                token: self.token()?,
+                value: self.literal()?,
+            }
+            .into(),
+            6 => Unary {
+                op: self.tag(&UNARY_OPS)?,
+                right: self.alloc()?,
+            }
+            .into(),
+            7 => Variable {
+                var: self.token()?,
+                binding: self.binding()?.into(),
+            }
+            .into(),
+            _ => return Err(CacheError::Malformed),
+        };
+        Ok(expr)
+    }
+
+    /// An operand, in the arena.
+    fn alloc(&mut self) -> Result<&'src ExprAst<'src>, CacheError> {
+        let expr = self.expr()?;
+        Ok(self.arena.alloc(expr))
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fmt, str::FromStr};
+
+/// A capability grants a script access to one group of native functions.
+/// Natives whose capability is not granted are never installed into the
+/// global environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Side-effect free helpers, e.g. `len`, `map`.
+    Pure,
+    /// Reading the system clock, e.g. `clock`.
+    Time,
+    /// Pseudo random numbers, e.g. `random`.
+    Random,
+    /// Reading files.
+    FsRead,
+    /// Creating and overwriting files.
+    FsWrite,
+    /// Reading the process environment.
+    Env,
+    /// Controlling the host process.
+    Process,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 7] = [
+        Self::Pure,
+        Self::Time,
+        Self::Random,
+        Self::FsRead,
+        Self::FsWrite,
+        Self::Env,
+        Self::Process,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pure => "pure",
+            Self::Time => "time",
+            Self::Random => "random",
+            Self::FsRead => "fs_read",
+            Self::FsWrite => "fs_write",
+            Self::Env => "env",
+            Self::Process => "process",
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|cap| cap.name() == s)
+            .ok_or_else(|| format!("unknown capability '{s}'"))
+    }
+}
+
+/// Set of capabilities granted to a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    /// No natives at all.
+    pub const fn none() -> Self {
+        Self(0)
+    }
+
+    /// Every capability, including environment and process access.
+    pub fn all() -> Self {
+        Capability::ALL.into_iter().collect()
+    }
+
+    pub fn with(mut self, cap: Capability) -> Self {
+        self.insert(cap);
+        self
+    }
+
+    pub fn insert(&mut self, cap: Capability) {
+        self.0 |= cap.bit();
+    }
+
+    pub fn contains(&self, cap: Capability) -> bool {
+        self.0 & cap.bit() != 0
+    }
+}
+
+/// By default, scripts can see pure helpers, the clock and random numbers,
+/// but nothing that touches the host.
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::none()
+            .with(Capability::Pure)
+            .with(Capability::Time)
+            .with(Capability::Random)
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<T: IntoIterator<Item = Capability>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::none(), Self::with)
+    }
+}
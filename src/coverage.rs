@@ -0,0 +1,132 @@
+use std::{cell::RefCell, collections::BTreeMap, io::Write, rc::Rc};
+
+use crate::{
+    env::{Env, RuntimeError},
+    runtime::Hook,
+    statement::{If, StmtAst},
+};
+
+/// Hits of the branches of an `if`: the body, then the else branch, taken
+/// when there is none as well.
+type Branches = [usize; 2];
+
+#[derive(Debug, Default)]
+struct State {
+    /// Hits of each line a statement begins at.
+    lines: BTreeMap<usize, usize>,
+    /// Branches of each `if`, by the position of its condition, which tells
+    /// apart the ones on the same line.
+    branches: BTreeMap<(usize, usize), Branches>,
+}
+
+/// Hook recording which lines run, and which branches of each `if` are
+/// taken, to be written as LCOV.
+#[derive(Debug, Default)]
+pub(crate) struct Coverage {
+    state: RefCell<State>,
+}
+
+impl Coverage {
+    /// Writes the record of the source file at `path`.
+    pub(crate) fn write_lcov(&self, path: &str, out: &mut dyn Write) {
+        let state = self.state.borrow();
+        writeln!(out, "TN:").unwrap();
+        writeln!(out, "SF:{path}").unwrap();
+
+        let mut block = (0, 0);
+        for (&(line, _), branches) in &state.branches {
+            // Blocks are numbered from 0 on each line.
+            block = if block.0 == line {
+                (line, block.1 + 1)
+            } else {
+                (line, 0)
+            };
+            // A branch is '-' when its `if` never ran.
+            let ran = branches.iter().sum::<usize>() > 0;
+            for (branch, hits) in branches.iter().enumerate() {
+                let taken = if ran {
+                    hits.to_string()
+                } else {
+                    "-".to_string()
+                };
+                writeln!(out, "BRDA:{line},{},{branch},{taken}", block.1).unwrap();
+            }
+        }
+        let branches = state.branches.values().flatten();
+        writeln!(out, "BRF:{}", branches.clone().count()).unwrap();
+        writeln!(out, "BRH:{}", branches.filter(|&&hits| hits > 0).count()).unwrap();
+
+        for (line, hits) in &state.lines {
+            writeln!(out, "DA:{line},{hits}").unwrap();
+        }
+        writeln!(out, "LF:{}", state.lines.len()).unwrap();
+        let hit = state.lines.values().filter(|&&hits| hits > 0).count();
+        writeln!(out, "LH:{hit}").unwrap();
+        writeln!(out, "end_of_record").unwrap();
+    }
+}
+
+impl<'src> Hook<'src> for Coverage {
+    /// Every statement and `if` starts without hits, to be reported even if
+    /// it never runs.
+    fn start(&self, stmts: &[StmtAst<'src>]) {
+        let mut state = self.state.borrow_mut();
+        for stmt in stmts {
+            state.add(stmt);
+        }
+    }
+
+    fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        _env: &Rc<RefCell<Env<'src>>>,
+        _stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        *self
+            .state
+            .borrow_mut()
+            .lines
+            .entry(stmt.start_line())
+            .or_default() += 1;
+        Ok(())
+    }
+
+    fn branch(&self, if_stmt: &If<'src>, taken: bool) {
+        let token = if_stmt.condition.start_token();
+        let mut state = self.state.borrow_mut();
+        let branches = state
+            .branches
+            .entry((token.line, token.column))
+            .or_default();
+        branches[usize::from(!taken)] += 1;
+    }
+}
+
+impl State {
+    fn add(&mut self, stmt: &StmtAst) {
+        self.lines.entry(stmt.start_line()).or_default();
+        match stmt {
+            StmtAst::Block(block) => block.inner.iter().for_each(|stmt| self.add(stmt)),
+            StmtAst::If(if_stmt) => {
+                let token = if_stmt.condition.start_token();
+                self.branches.entry((token.line, token.column)).or_default();
+                self.add(&if_stmt.body);
+                if let Some(else_body) = if_stmt.else_body.as_ref() {
+                    self.add(else_body);
+                }
+            }
+            StmtAst::While(while_stmt) => self.add(&while_stmt.body),
+            StmtAst::For(for_stmt) => {
+                if let Some(initializer) = for_stmt.initializer.as_ref() {
+                    self.add(initializer);
+                }
+                self.add(&for_stmt.body);
+            }
+            StmtAst::FunctionDef(def) => def.body.iter().for_each(|stmt| self.add(stmt)),
+            StmtAst::Expression(_)
+            | StmtAst::Print(_)
+            | StmtAst::VarDecl(_)
+            | StmtAst::Return(_) => {}
+        }
+    }
+}
@@ -0,0 +1,513 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    fs,
+    io::{self, BufRead, LineWriter, Write},
+    process::ExitCode,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    debugger::SameLine,
+    env::{Env, RuntimeError},
+    expr::ExprArena,
+    json::Json,
+    literal::LoxValue,
+    lsp::{read_message, write_message},
+    run,
+    runtime::{Call, Hook, Runtime},
+    statement::StmtAst,
+    RunOptions,
+};
+
+/// Lox runs on a single thread.
+const THREAD_ID: usize = 1;
+/// Variables reference of the globals. The locals of the frame at depth `d`
+/// are referenced by `LOCALS + d`.
+const GLOBALS: usize = 1;
+const LOCALS: usize = 2;
+
+/// Connection to the client, shared by the session and the hook which
+/// serves the requests while the program is stopped.
+struct Client<R, W> {
+    input: R,
+    output: W,
+    /// Sequence number of the last message sent.
+    seq: usize,
+    breakpoints: BTreeSet<usize>,
+    disconnected: bool,
+}
+
+type Shared<R, W> = Rc<RefCell<Client<R, W>>>;
+
+impl<R: BufRead, W: Write> Client<R, W> {
+    /// Next request, or `None` at the end of input. Malformed messages are
+    /// skipped, as the protocol has no way to answer them.
+    fn read(&mut self) -> Option<Json> {
+        loop {
+            let body = read_message(&mut self.input).ok()??;
+            if let Ok(request) = Json::parse(&body) {
+                return Some(request);
+            }
+        }
+    }
+
+    fn respond(&mut self, request: &Json, result: Result<Json, String>) {
+        let (success, key, value) = match result {
+            Ok(body) => (true, "body", body),
+            Err(message) => (false, "message", message.into()),
+        };
+        let request_seq = request.get("seq").cloned().unwrap_or(Json::Null);
+        let command = request.get("command").cloned().unwrap_or(Json::Null);
+        self.send([
+            ("type", "response".into()),
+            ("request_seq", request_seq),
+            ("success", success.into()),
+            ("command", command),
+            (key, value),
+        ]);
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send([
+            ("type", "event".into()),
+            ("event", event.into()),
+            ("body", body),
+        ]);
+    }
+
+    /// Sends the message of the fields, preceded by the next `seq`.
+    fn send<const N: usize>(&mut self, fields: [(&str, Json); N]) {
+        self.seq += 1;
+        let mut message = vec![("seq".to_string(), self.seq.into())];
+        message.extend(fields.map(|(key, value)| (key.to_string(), value)));
+        write_message(&mut self.output, &Json::Object(message));
+    }
+
+    /// Replaces the breakpoints. Lines are all of one program, so the source
+    /// of the arguments is not checked.
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let lines: Vec<usize> = arguments
+            .get("breakpoints")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|breakpoint| breakpoint.get("line")?.as_f64())
+            .map(|line| line as usize)
+            .collect();
+        self.breakpoints = lines.iter().copied().collect();
+
+        let breakpoints = lines
+            .into_iter()
+            .map(|line| Json::object([("verified", true.into()), ("line", line.into())]))
+            .collect::<Vec<_>>();
+        Ok(Json::object([("breakpoints", breakpoints.into())]))
+    }
+}
+
+/// Program given by the 'launch' request.
+struct Launch {
+    path: String,
+    src: String,
+    stop_on_entry: bool,
+    options: RunOptions,
+}
+
+impl Launch {
+    fn new(arguments: &Json) -> Result<Self, String> {
+        let path = arguments
+            .get("program")
+            .and_then(Json::as_str)
+            .ok_or("Missing the 'program' to launch.")?;
+        let src =
+            fs::read_to_string(path).map_err(|err| format!("Could not read '{path}': {err}"))?;
+        let args = arguments
+            .get("args")
+            .and_then(Json::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|arg| arg.as_str().map(str::to_string))
+            .collect();
+
+        Ok(Self {
+            path: path.to_string(),
+            src,
+            stop_on_entry: arguments.get("stopOnEntry") == Some(&Json::Bool(true)),
+            options: RunOptions {
+                args,
+                ..RunOptions::default()
+            },
+        })
+    }
+}
+
+/// Serves the Debug Adapter Protocol for the program of the 'launch'
+/// request. It runs once both 'launch' and 'configurationDone' are
+/// received, so that the breakpoints are set before.
+pub(crate) fn serve<R: BufRead, W: Write>(input: R, output: W) -> ExitCode {
+    let client = Rc::new(RefCell::new(Client {
+        input,
+        output,
+        seq: 0,
+        breakpoints: BTreeSet::new(),
+        disconnected: false,
+    }));
+    let mut launch = None;
+    let mut configured = false;
+    let mut launched = false;
+
+    loop {
+        let Some(request) = client.borrow_mut().read() else {
+            // The client went away without disconnecting.
+            return ExitCode::FAILURE;
+        };
+        let command = request.get("command").and_then(Json::as_str).unwrap_or("");
+        let arguments = request.get("arguments").unwrap_or(&Json::Null);
+
+        let result = match command {
+            "initialize" => Ok(capabilities()),
+            "launch" => Launch::new(arguments).map(|program| {
+                launch = Some(program);
+                Json::object([])
+            }),
+            "configurationDone" => {
+                configured = true;
+                Ok(Json::object([]))
+            }
+            "setBreakpoints" => client.borrow_mut().set_breakpoints(arguments),
+            "threads" => Ok(threads()),
+            "disconnect" => {
+                client.borrow_mut().respond(&request, Ok(Json::object([])));
+                return ExitCode::SUCCESS;
+            }
+            "stackTrace" | "scopes" | "variables" | "continue" | "next" | "stepIn" | "stepOut" => {
+                Err("The program is not stopped.".to_string())
+            }
+            _ => Err(format!("Unknown command '{command}'.")),
+        };
+        client.borrow_mut().respond(&request, result);
+        if command == "initialize" {
+            client.borrow_mut().event("initialized", Json::object([]));
+        }
+
+        if let (true, false, Some(program)) = (configured, launched, &launch) {
+            launched = true;
+            let exit_code = debug(&client, program);
+
+            let mut client = client.borrow_mut();
+            if client.disconnected {
+                return ExitCode::SUCCESS;
+            }
+            client.event("exited", Json::object([("exitCode", exit_code.into())]));
+            client.event("terminated", Json::object([]));
+        }
+    }
+}
+
+/// Runs the program until it ends or the client disconnects, and returns
+/// its exit code.
+fn debug<R: BufRead, W: Write>(client: &Shared<R, W>, program: &Launch) -> usize {
+    let mut stdout = LineWriter::new(Output::new(client, "stdout"));
+    let mut stderr = LineWriter::new(Output::new(client, "stderr"));
+
+    let arena = ExprArena::default();
+    let adapter = Adapter::new(client.clone(), program);
+    let runtime = Runtime::new(program.options.seed).with_hook(adapter);
+    let exit_code = run(
+        &program.src,
+        &arena,
+        &program.options,
+        runtime,
+        &mut stdout,
+        &mut stderr,
+    );
+    stdout.flush().unwrap();
+    stderr.flush().unwrap();
+    usize::from(exit_code)
+}
+
+/// Output of the program, sent to the client as 'output' events.
+struct Output<'a, R, W> {
+    client: &'a Shared<R, W>,
+    category: &'static str,
+}
+
+impl<'a, R, W> Output<'a, R, W> {
+    fn new(client: &'a Shared<R, W>, category: &'static str) -> Self {
+        Self { client, category }
+    }
+}
+
+impl<R: BufRead, W: Write> Write for Output<'_, R, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let body = Json::object([
+            ("category", self.category.into()),
+            ("output", String::from_utf8_lossy(buf).into_owned().into()),
+        ]);
+        self.client.borrow_mut().event("output", body);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How far to run before stopping again.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    /// At the first line, if asked to stop on entry.
+    Entry,
+    Step,
+    /// Until a line of a call at most this deep.
+    Next(usize),
+    /// Until a line of a call less deep than this.
+    Out(usize),
+    Continue,
+}
+
+/// A function being called, or the script at the bottom.
+struct Frame<'src> {
+    name: String,
+    line: usize,
+    /// Environment of the last statement run in the frame.
+    env: Weak<RefCell<Env<'src>>>,
+}
+
+/// Hook stopping the run at breakpoints and steps, where it serves the
+/// requests inspecting the program until one resumes it.
+struct Adapter<'src, R, W> {
+    client: Shared<R, W>,
+    path: String,
+    mode: RefCell<Mode>,
+    frames: RefCell<Vec<Frame<'src>>>,
+    same_line: RefCell<SameLine>,
+}
+
+impl<'src, R: BufRead, W: Write> Adapter<'src, R, W> {
+    fn new(client: Shared<R, W>, program: &Launch) -> Self {
+        let script = Frame {
+            name: "<script>".to_string(),
+            line: 1,
+            env: Weak::new(),
+        };
+        Self {
+            client,
+            path: program.path.clone(),
+            mode: RefCell::new(if program.stop_on_entry {
+                Mode::Entry
+            } else {
+                Mode::Continue
+            }),
+            frames: RefCell::new(vec![script]),
+            same_line: RefCell::default(),
+        }
+    }
+
+    /// Serves requests until one resumes the run.
+    fn stop(&self, reason: &str) -> Result<(), RuntimeError> {
+        let mut client = self.client.borrow_mut();
+        client.event(
+            "stopped",
+            Json::object([
+                ("reason", reason.into()),
+                ("threadId", THREAD_ID.into()),
+                ("allThreadsStopped", true.into()),
+            ]),
+        );
+
+        loop {
+            let Some(request) = client.read() else {
+                return Err(RuntimeError::Exit(0));
+            };
+            let command = request.get("command").and_then(Json::as_str).unwrap_or("");
+            let arguments = request.get("arguments").unwrap_or(&Json::Null);
+
+            let depth = self.frames.borrow().len() - 1;
+            let mode = match command {
+                "continue" => Some(Mode::Continue),
+                "next" => Some(Mode::Next(depth)),
+                "stepIn" => Some(Mode::Step),
+                "stepOut" => Some(Mode::Out(depth)),
+                _ => None,
+            };
+            if let Some(mode) = mode {
+                *self.mode.borrow_mut() = mode;
+                let body = match mode {
+                    Mode::Continue => Json::object([("allThreadsContinued", true.into())]),
+                    _ => Json::object([]),
+                };
+                client.respond(&request, Ok(body));
+                return Ok(());
+            }
+
+            let result = match command {
+                "threads" => Ok(threads()),
+                "stackTrace" => Ok(self.stack_trace()),
+                "scopes" => self.scopes(arguments),
+                "variables" => self.variables(arguments),
+                "setBreakpoints" => client.set_breakpoints(arguments),
+                "disconnect" => {
+                    client.disconnected = true;
+                    client.respond(&request, Ok(Json::object([])));
+                    return Err(RuntimeError::Exit(0));
+                }
+                _ => Err(format!("Unknown command '{command}'.")),
+            };
+            client.respond(&request, result);
+        }
+    }
+
+    fn stack_trace(&self) -> Json {
+        let frames = self.frames.borrow();
+        let stack_frames = frames
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(depth, frame)| {
+                Json::object([
+                    ("id", depth.into()),
+                    ("name", frame.name.as_str().into()),
+                    (
+                        "source",
+                        Json::object([("path", self.path.as_str().into())]),
+                    ),
+                    ("line", frame.line.into()),
+                    ("column", 1.into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Json::object([
+            ("totalFrames", frames.len().into()),
+            ("stackFrames", stack_frames.into()),
+        ])
+    }
+
+    fn scopes(&self, arguments: &Json) -> Result<Json, String> {
+        let depth = arguments
+            .get("frameId")
+            .and_then(Json::as_f64)
+            .map(|id| id as usize)
+            .filter(|&depth| depth < self.frames.borrow().len())
+            .ok_or("Unknown frame.")?;
+
+        let scope = |name: &str, reference: usize| {
+            Json::object([
+                ("name", name.into()),
+                ("variablesReference", reference.into()),
+                ("expensive", false.into()),
+            ])
+        };
+        let scopes = vec![scope("Locals", LOCALS + depth), scope("Globals", GLOBALS)];
+        Ok(Json::object([("scopes", scopes.into())]))
+    }
+
+    fn variables(&self, arguments: &Json) -> Result<Json, String> {
+        let reference = arguments
+            .get("variablesReference")
+            .and_then(Json::as_f64)
+            .map_or(0, |reference| reference as usize);
+        let frames = self.frames.borrow();
+
+        // Any environment leads to the globals.
+        let frame = match reference {
+            GLOBALS => frames.first(),
+            _ => reference
+                .checked_sub(LOCALS)
+                .and_then(|depth| frames.get(depth)),
+        }
+        .ok_or("Unknown variables reference.")?;
+
+        let variables = frame
+            .env
+            .upgrade()
+            .map(|env| variables(&env, reference == GLOBALS))
+            .unwrap_or_default();
+        Ok(Json::object([("variables", variables.into())]))
+    }
+}
+
+impl<'src, R: BufRead, W: Write> Hook<'src> for Adapter<'src, R, W> {
+    fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        env: &Rc<RefCell<Env<'src>>>,
+        _stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        let line = stmt.start_line();
+        let depth = {
+            let mut frames = self.frames.borrow_mut();
+            let frame = frames.last_mut().unwrap();
+            frame.line = line;
+            frame.env = Rc::downgrade(env);
+            frames.len() - 1
+        };
+        if !self.same_line.borrow_mut().first(stmt, depth) {
+            return Ok(());
+        }
+
+        let reason = if self.client.borrow().breakpoints.contains(&line) {
+            "breakpoint"
+        } else {
+            match *self.mode.borrow() {
+                Mode::Entry => "entry",
+                Mode::Step => "step",
+                Mode::Next(max_depth) if depth <= max_depth => "step",
+                Mode::Out(max_depth) if depth < max_depth => "step",
+                _ => return Ok(()),
+            }
+        };
+        self.stop(reason)
+    }
+
+    fn enter_call(&self, call: &Call, _arguments: &[LoxValue<'src>]) {
+        let mut frames = self.frames.borrow_mut();
+        frames.last_mut().unwrap().line = call.line;
+        frames.push(Frame {
+            name: call.name.to_string(),
+            line: call.line,
+            env: Weak::new(),
+        });
+    }
+
+    fn leave_call(&self, _result: Option<&LoxValue<'src>>) {
+        self.frames.borrow_mut().pop();
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object([("supportsConfigurationDoneRequest", true.into())])
+}
+
+fn threads() -> Json {
+    let thread = Json::object([("id", THREAD_ID.into()), ("name", "main".into())]);
+    Json::object([("threads", vec![thread].into())])
+}
+
+/// Variables of the scopes from `env` outwards, the innermost first. Either
+/// only the global scope is read, or every scope but it. Natives are left
+/// out.
+fn variables(env: &Rc<RefCell<Env>>, globals: bool) -> Vec<Json> {
+    let mut names = Vec::new();
+    let mut variables = Vec::new();
+    let mut current = Some(env.clone());
+
+    while let Some(env) = current {
+        let env = env.borrow();
+        if env.is_global() == globals {
+            for (name, value) in env.variables() {
+                if names.contains(&name) || matches!(value, LoxValue::RustFunction(_)) {
+                    continue;
+                }
+                variables.push(Json::object([
+                    ("name", name.into()),
+                    ("value", value.inspect().into()),
+                    ("variablesReference", 0.into()),
+                ]));
+                names.push(name);
+            }
+        }
+        current = env.parent.clone();
+    }
+    variables
+}
@@ -0,0 +1,285 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use crate::{
+    env::{Env, Evaluatable, RuntimeError},
+    expr::{ExprArena, ExprParser},
+    literal::LoxValue,
+    runtime::{Call, Hook},
+    statement::StmtAst,
+    token::{TokenStream, Tokenizer},
+};
+
+const HELP: &str = "\
+break <line>  Stop before the line runs
+step          Run to the next line, entering calls
+next          Run to the next line, stepping over calls
+continue      Run to the next breakpoint
+locals        Show the variables in scope
+backtrace     Show the calls leading here
+print <expr>  Evaluate an expression here
+quit          Stop the program";
+
+/// How far to run before the next prompt.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Step,
+    /// Until a line of a call at most this deep.
+    Next(usize),
+    Continue,
+}
+
+struct State {
+    mode: Mode,
+    breakpoints: BTreeSet<usize>,
+    /// Name of each function being called, and the line it is called at.
+    frames: Vec<(String, usize)>,
+    same_line: SameLine,
+    /// Whether an expression of `print` is being evaluated, which must not
+    /// stop, e.g. at a breakpoint in a function it calls.
+    evaluating: bool,
+}
+
+/// Statements run since the last one on another line or at another depth,
+/// so that statements on the same line, e.g. `if (x) return;`, stop only
+/// once. A statement run again starts the next iteration of a loop on the
+/// line, e.g. `while (i < 3) i = i + 1;`, which stops again.
+#[derive(Default)]
+pub(crate) struct SameLine {
+    line: usize,
+    depth: usize,
+    stmts: Vec<*const ()>,
+}
+
+impl SameLine {
+    /// Whether the statement is the first run on its line, where it may stop.
+    pub(crate) fn first(&mut self, stmt: &StmtAst, depth: usize) -> bool {
+        let line = stmt.start_line();
+        let id = stmt as *const StmtAst as *const ();
+        if (line, depth) == (self.line, self.depth) && !self.stmts.contains(&id) {
+            self.stmts.push(id);
+            return false;
+        }
+        *self = Self {
+            line,
+            depth,
+            stmts: vec![id],
+        };
+        true
+    }
+}
+
+/// Interactive debugger, which prompts for commands before running a line
+/// where it should stop. It stops first at the first line.
+pub(crate) struct Debugger<'src, R: BufRead> {
+    lines: Vec<String>,
+    /// The program's, which keeps the expressions given to `print`, as
+    /// values of the program may borrow them.
+    arena: &'src ExprArena<'src>,
+    input: RefCell<R>,
+    state: RefCell<State>,
+}
+
+impl<'src, R: BufRead> Debugger<'src, R> {
+    pub(crate) fn new(src: &str, arena: &'src ExprArena<'src>, input: R) -> Self {
+        Self {
+            lines: src.lines().map(str::to_string).collect(),
+            arena,
+            input: RefCell::new(input),
+            state: RefCell::new(State {
+                mode: Mode::Step,
+                breakpoints: BTreeSet::new(),
+                frames: Vec::new(),
+                same_line: SameLine::default(),
+                evaluating: false,
+            }),
+        }
+    }
+
+    /// Prompts until a command resumes the run.
+    fn prompt(
+        &self,
+        line: usize,
+        env: &Rc<RefCell<Env<'src>>>,
+        out: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        let text = self.lines.get(line - 1).map_or("", |text| text.trim());
+        writeln!(out, "[line {line}] {text}").unwrap();
+
+        loop {
+            write!(out, "(lox) ").unwrap();
+            out.flush().unwrap();
+
+            let mut command = String::new();
+            if self.input.borrow_mut().read_line(&mut command).unwrap_or(0) == 0 {
+                // Without input, the rest runs as if there is no debugger.
+                let mut state = self.state.borrow_mut();
+                state.mode = Mode::Continue;
+                state.breakpoints.clear();
+                writeln!(out).unwrap();
+                return Ok(());
+            }
+
+            let (command, argument) = command
+                .trim()
+                .split_once(' ')
+                .map_or((command.trim(), ""), |(c, a)| (c, a.trim()));
+            let mut state = self.state.borrow_mut();
+            match command {
+                "break" | "b" => match argument.parse::<usize>() {
+                    Ok(line) => {
+                        state.breakpoints.insert(line);
+                        writeln!(out, "Breakpoint at line {line}.").unwrap();
+                    }
+                    Err(_) => writeln!(out, "Usage: break <line>").unwrap(),
+                },
+                "step" | "s" => {
+                    state.mode = Mode::Step;
+                    return Ok(());
+                }
+                "next" | "n" => {
+                    state.mode = Mode::Next(state.frames.len());
+                    return Ok(());
+                }
+                "continue" | "c" => {
+                    state.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "locals" | "l" => locals(env, out),
+                "backtrace" | "bt" => {
+                    for (depth, (name, line)) in backtrace(&state.frames, line).enumerate() {
+                        writeln!(out, "#{depth} {name} at line {line}").unwrap();
+                    }
+                }
+                "print" | "p" => {
+                    drop(state);
+                    self.print(argument, env, out);
+                }
+                "quit" | "q" => return Err(RuntimeError::Exit(0)),
+                "help" | "h" => writeln!(out, "{HELP}").unwrap(),
+                "" => {}
+                _ => writeln!(
+                    out,
+                    "Unknown command '{command}'. Type 'help' for the commands."
+                )
+                .unwrap(),
+            }
+        }
+    }
+
+    /// Evaluates the expression against the current environment.
+    fn print(&self, expr: &str, env: &Rc<RefCell<Env<'src>>>, out: &mut dyn Write) {
+        let src = self.arena.text(expr);
+        let mut stream = TokenStream::new(Tokenizer::new(src));
+        let parsed = ExprParser::new(&mut stream, self.arena).parse();
+        let rest = stream.peek().src;
+        let expired = stream.expired();
+        if let Err(err) = stream.finish() {
+            return writeln!(out, "{}", err.kind).unwrap();
+        }
+
+        let expr = match parsed {
+            Ok(_) if !expired => {
+                return writeln!(out, "Error at '{rest}': Expected end of expression.").unwrap();
+            }
+            Ok(expr) => expr,
+            Err(err) => return writeln!(out, "{err}").unwrap(),
+        };
+
+        // Anything printed while evaluating, e.g. by a call, comes first.
+        let mut printed = Vec::new();
+        self.state.borrow_mut().evaluating = true;
+        let result = expr.eval(env.clone(), &mut printed);
+        self.state.borrow_mut().evaluating = false;
+        out.write_all(&printed).unwrap();
+        match result {
+            Ok(value) => writeln!(out, "{value}").unwrap(),
+            Err(err) => writeln!(out, "{}", err.kind).unwrap(),
+        }
+    }
+}
+
+impl<'src, R: BufRead> Hook<'src> for Debugger<'src, R> {
+    fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        env: &Rc<RefCell<Env<'src>>>,
+        stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        let line = stmt.start_line();
+        let stop = {
+            let mut state = self.state.borrow_mut();
+            if state.evaluating {
+                return Ok(());
+            }
+            let depth = state.frames.len();
+            if !state.same_line.first(stmt, depth) {
+                return Ok(());
+            }
+
+            let stop = match state.mode {
+                Mode::Step => true,
+                Mode::Next(max_depth) => depth <= max_depth,
+                Mode::Continue => false,
+            };
+            stop || state.breakpoints.contains(&line)
+        };
+
+        if stop {
+            self.prompt(line, env, stdout)?;
+        }
+        Ok(())
+    }
+
+    fn enter_call(&self, call: &Call, _arguments: &[LoxValue<'src>]) {
+        self.state
+            .borrow_mut()
+            .frames
+            .push((call.name.to_string(), call.line));
+    }
+
+    fn leave_call(&self, _result: Option<&LoxValue<'src>>) {
+        self.state.borrow_mut().frames.pop();
+    }
+}
+
+/// Variables of the scopes of the current call, the innermost first. At the
+/// top level, these are the globals except the natives.
+fn locals(env: &Rc<RefCell<Env>>, out: &mut dyn Write) {
+    let mut seen = Vec::new();
+    let mut current = Some(env.clone());
+
+    while let Some(env) = current {
+        let env = env.borrow();
+        for (name, value) in env.variables() {
+            if seen.contains(&name) || matches!(value, LoxValue::RustFunction(_)) {
+                continue;
+            }
+            writeln!(out, "{name} = {value}").unwrap();
+            seen.push(name);
+        }
+
+        // Stop at the closure of the function, unless already at the top.
+        current = match &env.parent {
+            Some(parent) if !parent.borrow().is_global() => Some(parent.clone()),
+            _ => None,
+        };
+    }
+
+    if seen.is_empty() {
+        writeln!(out, "No variables.").unwrap();
+    }
+}
+
+/// Each call from the innermost, with the line it is at.
+fn backtrace(frames: &[(String, usize)], line: usize) -> impl Iterator<Item = (&str, usize)> + '_ {
+    (0..=frames.len()).rev().map(move |i| {
+        let name = if i == 0 { "<script>" } else { &frames[i - 1].0 };
+        let line = frames.get(i).map_or(line, |(_, call_line)| *call_line);
+        (name, line)
+    })
+}
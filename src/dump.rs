@@ -0,0 +1,207 @@
+//! Tokens, statements and errors as JSON, for tools reading the output of
+//! `tokenize` and `parse` with `--format json`.
+
+use crate::{
+    error::{IntoLoxError, LoxError},
+    expr::ExprAst,
+    json::Json,
+    literal::{Literal, Number},
+    mac::tt,
+    statement::StmtAst,
+    token::Token,
+};
+
+/// e.g. `{"type":"NUMBER","lexeme":"1.50","literal":1.5,"line":1,"column":5}`.
+pub(crate) fn token(token: &Token) -> Json {
+    Json::object([
+        ("type", format!("{:?}", token.token_type).into()),
+        ("lexeme", token.src.into()),
+        ("literal", literal(token)),
+        ("line", token.line.into()),
+        ("column", token.column.into()),
+    ])
+}
+
+/// The value of a string, number, boolean or nil token, or null for the
+/// others.
+fn literal(token: &Token) -> Json {
+    match token.token_type {
+        tt!("string") => token.src.trim_matches('"').into(),
+        tt!("number") => token.src.parse::<Number>().unwrap().0.into(),
+        tt!("true") => true.into(),
+        tt!("false") => false.into(),
+        _ => Json::Null,
+    }
+}
+
+/// e.g. `{"code":"expected-expression","message":"Expected expression.",
+/// "span":{"line":3,"column":7,"end_column":8}}`, where the end is after the
+/// token.
+pub(crate) fn error<E: IntoLoxError>(code: &str, err: &LoxError<E>) -> Json {
+    let span = Json::object([
+        ("line", err.line.into()),
+        ("column", err.column.map_or(Json::Null, Json::from)),
+        ("end_column", err.end_column.map_or(Json::Null, Json::from)),
+    ]);
+    Json::object([
+        ("code", code.into()),
+        ("message", bare_message(&err.kind.to_string()).into()),
+        ("span", span),
+    ])
+}
+
+/// The message without its "Error:" or "Error at ...:" prefix, as the span
+/// tells where. What follows "at" may contain ": ", but the message does not.
+fn bare_message(message: &str) -> &str {
+    if let Some(rest) = message.strip_prefix("Error: ") {
+        rest
+    } else if message.starts_with("Error at ") {
+        message.rsplit_once(": ").map_or(message, |(_, rest)| rest)
+    } else {
+        message
+    }
+}
+
+/// Each node is an object whose "type" is the name of its AST type.
+pub(crate) fn stmts(stmts: &[StmtAst]) -> Json {
+    stmts.iter().map(stmt).collect::<Vec<_>>().into()
+}
+
+fn stmt(stmt: &StmtAst) -> Json {
+    match stmt {
+        StmtAst::Expression(v) => Json::object([
+            ("type", "Expression".into()),
+            ("line", stmt.start_line().into()),
+            ("expr", expr(&v.expr)),
+        ]),
+        StmtAst::Print(v) => Json::object([
+            ("type", "Print".into()),
+            ("line", v.start_line.into()),
+            ("expr", expr(&v.expr)),
+        ]),
+        StmtAst::VarDecl(v) => Json::object([
+            ("type", "VarDecl".into()),
+            ("line", v.start_line.into()),
+            ("name", v.var.start_token().src.into()),
+            ("value", optional(v.value.as_ref().map(expr))),
+        ]),
+        StmtAst::Block(v) => Json::object([
+            ("type", "Block".into()),
+            ("line", v.start_line.into()),
+            ("body", stmts(&v.inner)),
+        ]),
+        StmtAst::If(v) => Json::object([
+            ("type", "If".into()),
+            ("line", v.start_line.into()),
+            ("condition", expr(&v.condition)),
+            ("body", self::stmt(&v.body)),
+            (
+                "else_body",
+                optional(v.else_body.as_deref().map(self::stmt)),
+            ),
+        ]),
+        StmtAst::While(v) => Json::object([
+            ("type", "While".into()),
+            ("line", v.start_line.into()),
+            ("condition", expr(&v.condition)),
+            ("body", self::stmt(&v.body)),
+        ]),
+        StmtAst::For(v) => Json::object([
+            ("type", "For".into()),
+            ("line", v.start_line.into()),
+            (
+                "initializer",
+                optional(v.initializer.as_deref().map(self::stmt)),
+            ),
+            ("condition", optional(v.condition.as_ref().map(expr))),
+            ("increment", optional(v.increment.as_ref().map(expr))),
+            ("body", self::stmt(&v.body)),
+        ]),
+        StmtAst::FunctionDef(v) => Json::object([
+            ("type", "FunctionDef".into()),
+            ("line", v.start_line.into()),
+            ("name", v.name.src.into()),
+            (
+                "parameters",
+                v.arguments
+                    .iter()
+                    .map(|argument| argument.src.into())
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            ("body", stmts(&v.body)),
+        ]),
+        StmtAst::Return(v) => Json::object([
+            ("type", "Return".into()),
+            ("line", stmt.start_line().into()),
+            ("value", optional(v.expr.as_ref().map(expr))),
+        ]),
+    }
+}
+
+fn expr(expr: &ExprAst) -> Json {
+    match expr {
+        ExprAst::Assign(v) => Json::object([
+            ("type", "Assign".into()),
+            ("assignee", self::expr(v.assignee)),
+            ("value", self::expr(v.value)),
+        ]),
+        ExprAst::Binary(v) => Json::object([
+            ("type", "Binary".into()),
+            ("operator", v.op.to_string().into()),
+            ("left", self::expr(v.left)),
+            ("right", self::expr(v.right)),
+        ]),
+        ExprAst::FieldCall(v) => Json::object([
+            ("type", "FieldCall".into()),
+            ("object", self::expr(v.object)),
+            ("field", v.field.as_str().into()),
+        ]),
+        ExprAst::FunctionCall(v) => Json::object([
+            ("type", "FunctionCall".into()),
+            ("callee", self::expr(v.callee)),
+            (
+                "arguments",
+                v.arguments
+                    .iter()
+                    .map(self::expr)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+        ]),
+        ExprAst::Grouping(v) => {
+            Json::object([("type", "Grouping".into()), ("inner", self::expr(v.inner))])
+        }
+        ExprAst::LiteralExpr(v) => Json::object([
+            ("type", "Literal".into()),
+            ("value", value(&v.value)),
+            ("line", v.token.line.into()),
+            ("column", v.token.column.into()),
+        ]),
+        ExprAst::Unary(v) => Json::object([
+            ("type", "Unary".into()),
+            ("operator", v.op.to_string().into()),
+            ("right", self::expr(v.right)),
+        ]),
+        ExprAst::Variable(v) => Json::object([
+            ("type", "Variable".into()),
+            ("name", v.var.src.into()),
+            ("line", v.var.line.into()),
+            ("column", v.var.column.into()),
+        ]),
+    }
+}
+
+/// The value of a literal expression, which may be folded by `-O`.
+fn value(value: &Literal) -> Json {
+    match value {
+        Literal::Boolean(b) => (*b).into(),
+        Literal::Nil => Json::Null,
+        Literal::Number(n) => n.0.into(),
+        Literal::String(s) => s.to_string().into(),
+    }
+}
+
+fn optional(json: Option<Json>) -> Json {
+    json.unwrap_or(Json::Null)
+}
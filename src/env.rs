@@ -1,70 +1,184 @@
-use std::{cell::RefCell, collections::HashMap, io::Write, process::ExitCode, rc::Rc};
+use std::{cell::RefCell, io::Write, rc::Rc};
 
 use crate::{
+    capability::{Capabilities, Capability},
     error::{IntoLoxError, LoxError},
-    function::rust_clock_function,
+    interner::Symbol,
     literal::LoxValue,
+    native::NATIVES,
     rc_rc,
+    runtime::Runtime,
 };
 
+/// Where a variable lives, given by the
+/// [`Resolver`](crate::resolver::Resolver) before the program runs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum Binding {
+    /// Not resolved, e.g. in an expression typed in the debugger. It is
+    /// looked up by name, from the innermost environment.
+    #[default]
+    Unresolved,
+    /// In the global environment, at the symbol of the name.
+    Global(Symbol),
+    /// In the slot of the environment `depth` levels out from the current.
+    Local { depth: usize, slot: usize },
+}
+
+impl Binding {
+    /// The same variable, as seen from the parent environment.
+    fn outer(self) -> Self {
+        match self {
+            Self::Local { depth, slot } => Self::Local {
+                depth: depth - 1,
+                slot,
+            },
+            binding => binding,
+        }
+    }
+}
+
 /// Environment, which holds every variable-value bindings and reference to
 /// global stdout.
 pub(crate) struct Env<'src> {
     pub(crate) parent: Option<Rc<RefCell<Env<'src>>>>,
-    pub(crate) scope: HashMap<String, LoxValue<'src>>,
+    /// Variables along with their names, by slot, or by the symbol of the
+    /// name in the global environment. A slot not defined (yet), e.g. of
+    /// `if (false) var x;`, has an empty name.
+    pub(crate) slots: Vec<(&'src str, LoxValue<'src>)>,
+    /// Shared by the global environment and all of its children.
+    pub(crate) runtime: Rc<Runtime<'src>>,
 }
 
 impl<'src> Env<'src> {
-    /// Creates a global environment,
-    pub fn new() -> Rc<RefCell<Self>> {
+    /// Creates a global environment, with the natives that given
+    /// capabilities allow.
+    pub fn new(capabilities: Capabilities, runtime: Runtime<'src>) -> Rc<RefCell<Self>> {
         let env = rc_rc!(Self {
             parent: None,
-            scope: HashMap::new()
+            slots: Vec::new(),
+            runtime: Rc::new(runtime),
         });
+        env.borrow().runtime.heap.env(&env);
 
-        env.borrow_mut().set("clock", rust_clock_function().into());
+        for native in NATIVES {
+            if capabilities.contains(native.capability) {
+                env.borrow_mut()
+                    .define_global(native.name, native.to_rust_function().into());
+            }
+        }
         env
     }
 
     /// New child environment instance.
     pub fn from_parent(parent: Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
-        rc_rc!(Self {
-            parent: Some(parent.clone()),
-            scope: HashMap::new(),
-        })
+        let runtime = parent.borrow().runtime.clone();
+        let env = rc_rc!(Self {
+            parent: Some(parent),
+            slots: Vec::new(),
+            runtime: runtime.clone(),
+        });
+        runtime.heap.env(&env);
+        env
     }
 
-    /// Get value with given key. It loops through all parent scopes.
-    pub fn get(&self, key: &str) -> Option<LoxValue<'src>> {
-        if let Some(value) = self.scope.get(key) {
-            // This value had been defined in current scope.
-            Some(value.clone())
-        } else if let Some(parent_env) = &self.parent {
-            // This value had been defined in one of its parents' scope.
-            parent_env.borrow().get(key)
-        } else {
-            None
+    /// The runtime, if any hook observes the run. It is cloned, so that the
+    /// environment is not borrowed while the hooks run.
+    pub fn hooked_runtime(&self) -> Option<Rc<Runtime<'src>>> {
+        (!self.runtime.hooks.is_empty()).then(|| self.runtime.clone())
+    }
+
+    /// Get the value of the variable.
+    pub fn get(&self, binding: Binding, name: &str) -> Option<LoxValue<'src>> {
+        let slot = match binding {
+            Binding::Local { depth: 0, slot } => slot,
+            Binding::Global(symbol) if self.is_global() => symbol.0,
+            Binding::Unresolved => match self.position(name) {
+                Some(slot) => slot,
+                None => return self.parent.as_ref()?.borrow().get(binding, name),
+            },
+            _ => return self.parent.as_ref()?.borrow().get(binding.outer(), name),
+        };
+        match self.slots.get(slot) {
+            Some((name, value)) if !name.is_empty() => Some(value.clone()),
+            _ => None,
         }
     }
 
-    /// Initializes the key-value pair at current scope. It overwrites on
-    /// duplicated keys.
-    pub fn set(&mut self, key: &str, value: LoxValue<'src>) {
-        self.scope.insert(key.to_string(), value);
+    /// Initializes the variable at current scope. It overwrites on
+    /// duplicated names.
+    pub fn define(&mut self, binding: Binding, name: &'src str, value: LoxValue<'src>) {
+        let slot = match binding {
+            Binding::Local { slot, .. } => slot,
+            Binding::Global(symbol) => symbol.0,
+            Binding::Unresolved if self.is_global() => {
+                self.runtime.interner.borrow_mut().intern(name).0
+            }
+            Binding::Unresolved => self.slots.len(),
+        };
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, ("", LoxValue::default()));
+        }
+        self.slots[slot] = (name, value);
     }
 
-    /// Updates the value stored in the hashmap. If fails, returns false.
-    pub fn update(&mut self, key: &str, value: LoxValue<'src>) -> bool {
-        if let Some(existing_value) = self.scope.get_mut(key) {
-            *existing_value = value.clone();
-            true
-        } else if let Some(parent_env) = &self.parent {
-            parent_env.borrow_mut().update(key, value)
-        } else {
-            false
+    /// Initializes the variable in the global environment, by its name.
+    pub fn define_global(&mut self, name: &'src str, value: LoxValue<'src>) {
+        let symbol = self.runtime.interner.borrow_mut().intern(name);
+        self.define(Binding::Global(symbol), name, value);
+    }
+
+    /// Updates the value of the variable. If it is not defined, returns
+    /// false.
+    pub fn assign(&mut self, binding: Binding, name: &str, value: LoxValue<'src>) -> bool {
+        let slot = match binding {
+            Binding::Local { depth: 0, slot } => slot,
+            Binding::Global(symbol) if self.is_global() => symbol.0,
+            Binding::Unresolved => match self.position(name) {
+                Some(slot) => slot,
+                None => {
+                    return self
+                        .parent
+                        .as_ref()
+                        .is_some_and(|parent| parent.borrow_mut().assign(binding, name, value))
+                }
+            },
+            _ => {
+                return self
+                    .parent
+                    .as_ref()
+                    .is_some_and(|parent| parent.borrow_mut().assign(binding.outer(), name, value))
+            }
+        };
+        match self.slots.get_mut(slot) {
+            Some((name, existing_value)) if !name.is_empty() => {
+                *existing_value = value;
+                true
+            }
+            _ => false,
         }
     }
 
+    /// The latest slot of the name in this environment.
+    fn position(&self, name: &str) -> Option<usize> {
+        self.slots.iter().rposition(|(local, _)| *local == name)
+    }
+
+    /// Variables of this environment only, sorted by name. Of a name
+    /// declared twice, the latter is taken.
+    pub fn variables(&self) -> Vec<(&'src str, &LoxValue<'src>)> {
+        let mut variables: Vec<_> = self
+            .slots
+            .iter()
+            .rev()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (*name, value))
+            .collect();
+        // The sort is stable, so the latter of the same name stays first.
+        variables.sort_by_key(|(name, _)| *name);
+        variables.dedup_by_key(|(name, _)| *name);
+        variables
+    }
+
     #[inline]
     pub fn is_global(&self) -> bool {
         self.parent.is_none() // && self.depth == 0?
@@ -107,6 +221,9 @@ pub(crate) enum RuntimeError {
     #[error("Error: Undefined variable '{0}'.")]
     UndefinedVariable(String),
 
+    #[error("Error: Undefined variable '{0}'. It requires the '{1}' capability.")]
+    CapabilityDenied(String, Capability),
+
     #[error("Error: Cannot call '{0}'")]
     InvalidCallTarget(String),
 
@@ -118,10 +235,26 @@ pub(crate) enum RuntimeError {
 
     #[error("Error: Cannot return at global scope.")]
     ReturnAtGlobal,
+
+    #[error("Error: Index {0} is out of range for a list of length {1}.")]
+    IndexOutOfRange(String, usize),
+
+    #[error("Error: Invalid JSON: {0} at byte {1}.")]
+    InvalidJson(&'static str, usize),
+
+    #[error("Error: Cannot convert {0} to JSON.")]
+    NotJsonSerializable(String),
+
+    /// Not an actual error, but the script called `exit(code)`.
+    #[error("Exit with code {0}.")]
+    Exit(u8),
 }
 
 impl IntoLoxError for RuntimeError {
-    fn exit_code(&self) -> ExitCode {
-        ExitCode::from(70)
+    fn exit_status(&self) -> u8 {
+        match self {
+            Self::Exit(code) => *code,
+            _ => 70,
+        }
     }
 }
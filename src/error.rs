@@ -1,4 +1,6 @@
-use std::{fmt, io::Write, process::ExitCode};
+use std::{fmt, io::Write, ops::Range, process::ExitCode};
+
+use crate::token::Token;
 
 pub(crate) trait LoxResult {
     fn write_to_buffer<W1: Write, W2: Write>(
@@ -30,6 +32,10 @@ impl<T: fmt::Display, E: IntoLoxError> LoxResult for Result<T, LoxError<E>> {
 #[derive(Debug, thiserror::Error)]
 pub(crate) struct LoxError<E: IntoLoxError> {
     pub(crate) line: usize,
+    /// Known for errors of tokenizing and parsing, which are at a token.
+    pub(crate) column: Option<usize>,
+    /// After the last character of the token, as `column` counts.
+    pub(crate) end_column: Option<usize>,
     pub(crate) kind: E,
 }
 
@@ -41,10 +47,33 @@ impl<E: IntoLoxError> fmt::Display for LoxError<E> {
 
 pub trait IntoLoxError: Sized + std::error::Error {
     // Required method
-    fn exit_code(&self) -> ExitCode;
+    fn exit_status(&self) -> u8;
+
+    // Provided methods
+    fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.exit_status())
+    }
 
-    // Provided method
     fn at(self, line: usize) -> LoxError<Self> {
-        LoxError { line, kind: self }
+        LoxError {
+            line,
+            column: None,
+            end_column: None,
+            kind: self,
+        }
+    }
+
+    fn at_columns(self, line: usize, columns: Range<usize>) -> LoxError<Self> {
+        LoxError {
+            line,
+            column: Some(columns.start),
+            end_column: Some(columns.end),
+            kind: self,
+        }
+    }
+
+    fn at_token(self, token: &Token) -> LoxError<Self> {
+        let end_column = token.column + token.src.chars().count();
+        self.at_columns(token.line, token.column..end_column)
     }
 }
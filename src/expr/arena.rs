@@ -0,0 +1,37 @@
+use std::{cell::RefCell, rc::Rc};
+
+use typed_arena::Arena;
+
+use super::ExprAst;
+use crate::interner::Interner;
+
+/// Owns the expressions of a program, which refer to each other by
+/// reference, so that nothing is copied to share or to evaluate a subtree.
+/// It lives as long as the source, and is dropped with everything at once.
+#[derive(Default)]
+pub(crate) struct ExprArena<'src> {
+    exprs: Arena<ExprAst<'src>>,
+    /// Source given while the program runs, e.g. expressions the debugger
+    /// is asked to print, which has to live as long as the program's.
+    texts: Arena<u8>,
+    /// One copy of each string constant.
+    strings: RefCell<Interner>,
+}
+
+impl<'src> ExprArena<'src> {
+    pub(crate) fn alloc(&self, expr: ExprAst<'src>) -> &ExprAst<'src> {
+        self.exprs.alloc(expr)
+    }
+
+    /// A copy of the text, as long-lived as the expressions.
+    pub(crate) fn text(&self, text: &str) -> &str {
+        self.texts.alloc_str(text)
+    }
+
+    /// The shared copy of a string constant.
+    pub(crate) fn string(&self, s: &str) -> Rc<str> {
+        let mut strings = self.strings.borrow_mut();
+        let symbol = strings.intern(s);
+        strings.string(symbol)
+    }
+}
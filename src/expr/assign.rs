@@ -13,8 +13,8 @@ use crate::{
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Assign<'src> {
-    pub assignee: Box<ExprAst<'src>>,
-    pub value: Box<ExprAst<'src>>,
+    pub assignee: &'src ExprAst<'src>,
+    pub value: &'src ExprAst<'src>,
 }
 
 impl fmt::Display for Assign<'_> {
@@ -32,8 +32,8 @@ impl<'src> ExprParser<'src, '_> {
 
         let right = self.parse_within_binding_power(BindingPower::AssignRight)?;
         Ok(Assign {
-            assignee: Box::new(left),
-            value: Box::new(right),
+            assignee: self.alloc(left),
+            value: self.alloc(right),
         })
     }
 }
@@ -44,13 +44,17 @@ impl<'src> Evaluatable<'src> for Assign<'src> {
         env: Rc<RefCell<Env<'src>>>,
         stdout: &mut W,
     ) -> Result<LoxValue<'src>, LoxError<RuntimeError>> {
-        let name = match *self.assignee.clone() {
-            ExprAst::Variable(var) => var.var,
+        let (name, binding) = match self.assignee {
+            ExprAst::Variable(var) => (var.var, var.binding.get()),
             rest => return Err(InvalidAssignmentTarget(rest.to_string()).at(self.line())),
         };
-        let value = (*self.value).eval(env.clone(), stdout)?;
+        let value = self.value.eval(env.clone(), stdout)?;
 
-        if env.borrow_mut().update(name.src, value.clone()) {
+        if env.borrow_mut().assign(binding, name.src, value.clone()) {
+            let runtime = env.borrow().hooked_runtime();
+            if let Some(runtime) = runtime {
+                runtime.assign(name.src, &value, self.line());
+            }
             Ok(value)
         } else {
             Err(UndefinedVariable(name.src.to_string()).at(self.line()))
@@ -15,9 +15,9 @@ use crate::{
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Binary<'src> {
-    pub left: Box<ExprAst<'src>>,
+    pub left: &'src ExprAst<'src>,
     pub op: BinaryOp,
-    pub right: Box<ExprAst<'src>>,
+    pub right: &'src ExprAst<'src>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -82,36 +82,22 @@ impl fmt::Display for Binary<'_> {
 }
 
 impl<'a> ExprParser<'a, '_> {
-    /// If following token is a binary operator,
-    /// parse the right operand and return the binary expression,
-    /// consuming the operator and the right operand.
-    ///
-    /// Otherwise, it doesn't consume anything and returns `None`.
-    pub(super) fn try_parse_binary(
+    /// Parses the right operand of the binary operator `op`, which is the
+    /// next token, consuming both.
+    pub(super) fn parse_binary(
         &mut self,
         lhs: ExprAst<'a>,
-    ) -> Option<Result<Binary<'a>, ExprParseError>> {
-        let op = self.eat_binary_op()?;
+        op: BinaryOp,
+    ) -> Result<Binary<'a>, ExprParseError> {
+        self.token_stream.next(); // consume the operator
 
         let binding_power: (BindingPower, BindingPower) = op.into();
-        let right_binding_power = binding_power.1;
+        let right = self.parse_within_binding_power(binding_power.1)?;
 
-        let right = match self.parse_within_binding_power(right_binding_power) {
-            Ok(inner) => Box::new(inner),
-            Err(e) => return Some(Err(e)),
-        };
-
-        Some(Ok(Binary {
-            left: Box::new(lhs),
+        Ok(Binary {
+            left: self.alloc(lhs),
             op,
-            right,
-        }))
-    }
-
-    fn eat_binary_op(&mut self) -> Option<BinaryOp> {
-        let token_type = self.token_stream.peek().token_type;
-        BinaryOp::from_token_type(token_type).inspect(|_| {
-            self.token_stream.next();
+            right: self.alloc(right),
         })
     }
 }
@@ -129,28 +115,13 @@ macro_rules! number_operation {
     }};
 }
 
-/// Casts given expressions to String, and do operation.
-macro_rules! string_operation {
-    ($left:expr, $right:expr, $env:expr, $func:expr, $stdout:expr) => {{
-        let left = eval_and_cast_to_literal(&$left, $env.clone(), $stdout)?
-            .string_or(OperandMustBe("string").at($left.line()))?;
-
-        let right = eval_and_cast_to_literal(&$right, $env.clone(), $stdout)?
-            .string_or(OperandMustBe("string").at($right.line()))?;
-
-        Ok($crate::literal::LoxValue::Literal(
-            $func(left, right).into(),
-        ))
-    }};
-}
-
 impl<'a> Evaluatable<'a> for Binary<'a> {
     fn eval<W: Write>(
         &self,
         env: Rc<RefCell<Env<'a>>>,
         stdout: &mut W,
     ) -> Result<LoxValue<'a>, LoxError<RuntimeError>> {
-        let Self { left, op, right } = self.clone();
+        let Self { left, op, right } = *self;
 
         match op {
             BinaryOp::Star => {
@@ -161,10 +132,17 @@ impl<'a> Evaluatable<'a> for Binary<'a> {
                 number_operation!(left, right, env, |l, r| l / r, stdout)
             }
 
-            BinaryOp::Plus => match eval_and_cast_to_literal(&left, env.clone(), stdout)? {
-                Literal::Number(_) => number_operation!(left, right, env, |l, r| l + r, stdout),
-                Literal::String(_) => {
-                    string_operation!(left, right, env, |l: String, r: String| l + &r, stdout)
+            // The left side is evaluated once, and tells which operation it is.
+            BinaryOp::Plus => match eval_and_cast_to_literal(left, env.clone(), stdout)? {
+                Literal::Number(l) => {
+                    let r = eval_and_cast_to_literal(right, env, stdout)?
+                        .number_or(OperandMustBe("number").at(right.line()))?;
+                    Ok(LoxValue::Literal((l + r).into()))
+                }
+                Literal::String(l) => {
+                    let r = eval_and_cast_to_literal(right, env, stdout)?
+                        .string_or(OperandMustBe("string").at(right.line()))?;
+                    Ok(LoxValue::Literal(concat(l, r).into()))
                 }
                 _ => Err(OperandMustBe("two numbers or two strings").at(left.line())),
             },
@@ -201,10 +179,7 @@ impl<'a> Evaluatable<'a> for Binary<'a> {
             }
 
             BinaryOp::And => {
-                if !left
-                    .eval(env.clone(), stdout)?
-                    .is_literal_and(|l| l.is_truthy())
-                {
+                if !left.eval(env.clone(), stdout)?.is_truthy() {
                     Ok(LoxValue::Literal(Literal::Boolean(false)))
                 } else {
                     right.eval(env, stdout)
@@ -213,7 +188,7 @@ impl<'a> Evaluatable<'a> for Binary<'a> {
 
             BinaryOp::Or => {
                 let left = left.eval(env.clone(), stdout)?;
-                if left.is_literal_and(|l| l.is_truthy()) {
+                if left.is_truthy() {
                     Ok(left)
                 } else {
                     right.eval(env, stdout)
@@ -227,6 +202,14 @@ impl<'a> Evaluatable<'a> for Binary<'a> {
     }
 }
 
+/// The only place a string is built while running, in one allocation.
+fn concat(left: Rc<str>, right: Rc<str>) -> Rc<str> {
+    let mut s = String::with_capacity(left.len() + right.len());
+    s.push_str(&left);
+    s.push_str(&right);
+    s.into()
+}
+
 fn eval_and_cast_to_literal<'a, W: Write>(
     expr: &ExprAst<'a>,
     env: Rc<RefCell<Env<'a>>>,
@@ -1,5 +1,3 @@
-use std::process::ExitCode;
-
 use crate::error::IntoLoxError;
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -17,8 +15,20 @@ pub(crate) enum ExprParseError {
     InvalidFunctionArgument(String),
 }
 
+impl ExprParseError {
+    /// Stable identifier of the error, e.g. for JSON output.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::ExpectedClosingParenthesis => "expected-closing-parenthesis",
+            Self::ExpectedExpression(_) => "expected-expression",
+            Self::ExpectedFieldName(_) => "expected-field-name",
+            Self::InvalidFunctionArgument(_) => "invalid-function-argument",
+        }
+    }
+}
+
 impl IntoLoxError for ExprParseError {
-    fn exit_code(&self) -> ExitCode {
-        ExitCode::from(65)
+    fn exit_status(&self) -> u8 {
+        65
     }
 }
@@ -10,7 +10,7 @@ use crate::mac::tt;
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct FieldCall<'src> {
-    pub object: Box<ExprAst<'src>>,
+    pub object: &'src ExprAst<'src>,
     pub field: String,
 }
 
@@ -34,7 +34,7 @@ impl<'src> ExprParser<'src, '_> {
             .to_string();
 
         Ok(FieldCall {
-            object: Box::new(left),
+            object: self.alloc(left),
             field,
         })
     }
@@ -14,12 +14,13 @@ use crate::{
     function::Callable,
     literal::LoxValue,
     mac::tt,
+    runtime::Call,
 };
 
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionCall<'src> {
-    pub callee: Box<ExprAst<'src>>,
+    pub callee: &'src ExprAst<'src>,
     pub arguments: Vec<ExprAst<'src>>,
 }
 
@@ -75,12 +76,30 @@ impl<'src> ExprParser<'src, '_> {
         }
 
         Ok(FunctionCall {
-            callee: Box::new(lhs),
+            callee: self.alloc(lhs),
             arguments,
         })
     }
 }
 
+impl<'src> FunctionCall<'src> {
+    /// Calls the function, letting the hooks know.
+    fn call<W: Write>(
+        &self,
+        function: &impl Callable<'src>,
+        call: &Call,
+        arguments: Vec<LoxValue<'src>>,
+        env: Rc<RefCell<Env<'src>>>,
+        stdout: &mut W,
+    ) -> Result<LoxValue<'src>, LoxError<RuntimeError>> {
+        let runtime = env.borrow().runtime.clone();
+        runtime.enter_call(call, &arguments);
+        let result = function.call(arguments, env, stdout);
+        runtime.leave_call(result.as_ref().ok());
+        result.map_err(|err| err.at(self.line()))
+    }
+}
+
 impl<'src> Evaluatable<'src> for FunctionCall<'src> {
     fn eval<W: Write>(
         &self,
@@ -95,12 +114,24 @@ impl<'src> Evaluatable<'src> for FunctionCall<'src> {
 
         match self.callee.eval(env.clone(), stdout)? {
             LoxValue::Literal(l) => Err(InvalidCallTarget(l.to_string()).at(self.line())),
-            LoxValue::RustFunction(rf) => rf
-                .call(arguments, env.clone(), stdout)
-                .map_err(|err| err.at(self.line())),
-            LoxValue::LoxFunction(lf) => lf
-                .call(arguments, env.clone(), stdout)
-                .map_err(|err| err.at(self.line())),
+            LoxValue::List(l) => Err(InvalidCallTarget(l.to_string()).at(self.line())),
+            LoxValue::Map(m) => Err(InvalidCallTarget(m.to_string()).at(self.line())),
+            LoxValue::RustFunction(rf) => {
+                let call = Call {
+                    name: rf.name,
+                    defined_at: None,
+                    line: self.line(),
+                };
+                self.call(&rf, &call, arguments, env, stdout)
+            }
+            LoxValue::LoxFunction(lf) => {
+                let call = Call {
+                    name: lf.def.name.src,
+                    defined_at: Some(lf.def.start_line),
+                    line: self.line(),
+                };
+                self.call(&lf, &call, arguments, env, stdout)
+            }
         }
     }
 
@@ -15,7 +15,7 @@ use crate::{
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Grouping<'src> {
-    pub inner: Box<ExprAst<'src>>,
+    pub inner: &'src ExprAst<'src>,
 }
 
 impl fmt::Display for Grouping<'_> {
@@ -33,7 +33,7 @@ impl<'src> ExprParser<'src, '_> {
                 self.token_stream.next(); // Consume '('.
 
                 let inner = match self.parse() {
-                    Ok(inner) => Box::new(inner),
+                    Ok(inner) => self.alloc(inner),
                     Err(e) => return Some(Err(e)),
                 };
 
@@ -4,7 +4,7 @@ use super::ExprParser;
 use crate::{
     env::RuntimeError,
     error::LoxError,
-    expr::ExprParseError,
+    expr::{ExprArena, ExprParseError},
     literal::{Literal, LoxValue, Number},
     mac::tt,
     token::Token,
@@ -15,30 +15,31 @@ use crate::{
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct LiteralExpr<'src> {
     pub token: Token<'src>,
+    /// Made once when parsed. Strings are shared by every evaluation.
+    pub(crate) value: Literal,
 }
 
-impl LiteralExpr<'_> {
-    fn eval_to_literal(&self) -> Literal {
-        match self.token.token_type {
+impl<'src> LiteralExpr<'src> {
+    /// Strings are shared with the other constants of the same text.
+    fn new(token: Token<'src>, arena: &ExprArena) -> Self {
+        let value = match token.token_type {
             tt!("nil") => Literal::Nil,
             tt!("true") => Literal::Boolean(true),
             tt!("false") => Literal::Boolean(false),
             tt!("number") => {
-                let number = self.token.src.parse::<Number>().unwrap();
+                let number = token.src.parse::<Number>().unwrap();
                 Literal::Number(number)
             }
-            tt!("string") => {
-                let src = self.token.src.trim_matches('"').to_string();
-                Literal::String(src)
-            }
+            tt!("string") => arena.string(token.src.trim_matches('"')).into(),
             rest => unreachable!("LiteralExpr cannot be parsed from {rest:?}"),
-        }
+        };
+        Self { token, value }
     }
 }
 
 impl fmt::Display for LiteralExpr<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.eval_to_literal())
+        write!(f, "{}", self.value)
     }
 }
 
@@ -48,8 +49,7 @@ impl<'src> Evaluatable<'src> for LiteralExpr<'src> {
         _: Rc<RefCell<Env<'src>>>,
         _: &mut W,
     ) -> Result<LoxValue<'src>, LoxError<RuntimeError>> {
-        let value = self.eval_to_literal();
-        Ok(value.into())
+        Ok(self.value.clone().into())
     }
 
     fn line(&self) -> usize {
@@ -64,9 +64,7 @@ impl<'src> ExprParser<'src, '_> {
         let peeked = self.token_stream.peek();
         match peeked.token_type {
             tt!("nil") | tt!("true") | tt!("false") | tt!("number") | tt!("string") => {
-                Some(Ok(LiteralExpr {
-                    token: self.token_stream.next().clone(),
-                }))
+                Some(Ok(LiteralExpr::new(self.token_stream.next(), self.arena)))
             }
             _ => None,
         }
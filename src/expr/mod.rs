@@ -1,3 +1,4 @@
+mod arena;
 mod assign;
 mod binary;
 mod binding_power;
@@ -11,17 +12,25 @@ mod variable;
 
 use std::{cell::RefCell, fmt, io::Write, rc::Rc};
 
+use self::binding_power::BindingPower;
 pub(crate) use self::{
-    assign::Assign, binary::Binary, error::ExprParseError, field_call::FieldCall,
-    function_call::FunctionCall, grouping::Grouping, unary::Unary, variable::Variable,
+    arena::ExprArena,
+    assign::Assign,
+    binary::{Binary, BinaryOp},
+    error::ExprParseError,
+    field_call::FieldCall,
+    function_call::FunctionCall,
+    grouping::Grouping,
+    literal::LiteralExpr,
+    unary::{Unary, UnaryOp},
+    variable::Variable,
 };
-use self::{binding_power::BindingPower, literal::LiteralExpr};
 use crate::{
     env::{Env, Evaluatable, RuntimeError},
     error::{IntoLoxError, LoxError},
     literal::LoxValue,
     mac::{impl_from, tt},
-    token::TokenStream,
+    token::{Token, TokenStream},
 };
 
 /// NOTE: lifetime 'a denotes the lifetime of source code.
@@ -73,6 +82,44 @@ impl<'src> Evaluatable<'src> for ExprAst<'src> {
     }
 }
 
+impl<'src> ExprAst<'src> {
+    /// The line of the leftmost token.
+    pub(crate) fn start_line(&self) -> usize {
+        self.start_token().line
+    }
+
+    /// The leftmost recorded token. Parentheses and unary operators are not
+    /// recorded, so a grouping starts where its inner expression does.
+    pub(crate) fn start_token(&self) -> &Token<'src> {
+        match self {
+            Self::Assign(v) => v.assignee.start_token(),
+            Self::Binary(v) => v.left.start_token(),
+            Self::FieldCall(v) => v.object.start_token(),
+            Self::FunctionCall(v) => v.callee.start_token(),
+            Self::Grouping(v) => v.inner.start_token(),
+            Self::LiteralExpr(v) => &v.token,
+            Self::Unary(v) => v.right.start_token(),
+            Self::Variable(v) => &v.var,
+        }
+    }
+
+    /// The line of the rightmost recorded token. Unlike [`Evaluatable::line`]
+    /// it is known for every expression, as it does not depend on running.
+    /// Closing parentheses and field names are not recorded.
+    pub(crate) fn end_line(&self) -> usize {
+        match self {
+            Self::Assign(v) => v.value.end_line(),
+            Self::Binary(v) => v.right.end_line(),
+            Self::FieldCall(v) => v.object.end_line(),
+            Self::FunctionCall(v) => v.arguments.last().unwrap_or(v.callee).end_line(),
+            Self::Grouping(v) => v.inner.end_line(),
+            Self::LiteralExpr(v) => v.token.line,
+            Self::Unary(v) => v.right.end_line(),
+            Self::Variable(v) => v.var.line,
+        }
+    }
+}
+
 impl fmt::Display for ExprAst<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -92,15 +139,23 @@ impl fmt::Display for ExprAst<'_> {
 /// Generic 'b is for the lifetime of mutable reference of token stream.
 pub(crate) struct ExprParser<'src, 'mr> {
     token_stream: &'mr mut TokenStream<'src>,
+    arena: &'src ExprArena<'src>,
 }
 
 impl<'src, 'mr> ExprParser<'src, 'mr> {
-    pub(crate) fn new(token_stream: &'mr mut TokenStream<'src>) -> Self {
-        Self { token_stream }
+    pub(crate) fn new(
+        token_stream: &'mr mut TokenStream<'src>,
+        arena: &'src ExprArena<'src>,
+    ) -> Self {
+        Self {
+            token_stream,
+            arena,
+        }
     }
 
     pub(crate) fn parse_with_line(&mut self) -> Result<ExprAst<'src>, LoxError<ExprParseError>> {
-        self.parse().map_err(|err| err.at(self.token_stream.line()))
+        self.parse()
+            .map_err(|err| err.at_token(&self.token_stream.last))
     }
 
     /// Parse within the lowest binding power.
@@ -138,18 +193,19 @@ impl<'src, 'mr> ExprParser<'src, 'mr> {
                 tt!("(") => {
                     left = self.parse_function_call(left)?.into();
                 }
-                _ => {
-                    if let Some(binary) = self.try_parse_binary(left.clone()) {
-                        left = binary?.into();
-                    } else {
-                        break;
-                    }
-                }
+                _ => match BinaryOp::from_token_type(token_type) {
+                    Some(op) => left = self.parse_binary(left, op)?.into(),
+                    None => break,
+                },
             }
         }
         Ok(left)
     }
 
+    fn alloc(&self, expr: ExprAst<'src>) -> &'src ExprAst<'src> {
+        self.arena.alloc(expr)
+    }
+
     /// For the start of an expression, only literal, grouping, and unary are
     /// allowed. e.g. `42`, `(42)`, `!42`, `-42`
     fn parse_start_of_expr_ast(&mut self) -> Result<ExprAst<'src>, ExprParseError> {
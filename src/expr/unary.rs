@@ -16,7 +16,7 @@ use crate::{
 #[derive(Debug, Clone, PartialEq)]
 pub struct Unary<'src> {
     pub op: UnaryOp,
-    pub right: Box<ExprAst<'src>>,
+    pub right: &'src ExprAst<'src>,
 }
 
 impl fmt::Display for Unary<'_> {
@@ -57,7 +57,7 @@ impl<'src> ExprParser<'src, '_> {
         let op = self.eat_unary_op()?;
 
         let right = match self.parse_within_binding_power(BindingPower::Unary) {
-            Ok(inner) => Box::new(inner),
+            Ok(inner) => self.alloc(inner),
             Err(e) => return Some(Err(e)),
         };
 
@@ -90,7 +90,7 @@ impl<'src> Evaluatable<'src> for Unary<'src> {
                 }
             }
             UnaryOp::Bang => {
-                let literal: Literal = (!right.is_literal_and(|l| l.is_truthy())).into();
+                let literal: Literal = (!right.is_truthy()).into();
                 Ok(literal.into())
             }
         }
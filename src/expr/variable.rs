@@ -1,21 +1,29 @@
-use std::{cell::RefCell, fmt, io::Write, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    io::Write,
+    rc::Rc,
+};
 
 use super::ExprParser;
 use crate::{
     env::{
-        Env, Evaluatable,
+        Binding, Env, Evaluatable,
         RuntimeError::{self, *},
     },
     error::{IntoLoxError, LoxError},
     literal::LoxValue,
     mac::tt,
+    native::Native,
     token::Token,
 };
 
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Variable<'src> {
-    pub(crate) var: &'src Token<'src>,
+    pub(crate) var: Token<'src>,
+    /// Given by the resolver, which cannot replace the shared expression.
+    pub(crate) binding: Cell<Binding>,
 }
 
 impl fmt::Display for Variable<'_> {
@@ -30,6 +38,7 @@ impl<'src> ExprParser<'src, '_> {
         match &peeked.token_type {
             tt!("identifier") => Some(Variable {
                 var: self.token_stream.next(),
+                binding: Cell::new(Binding::Unresolved),
             }),
             _ => None,
         }
@@ -42,8 +51,11 @@ impl<'src> Evaluatable<'src> for Variable<'src> {
         env: Rc<RefCell<Env<'src>>>,
         _: &mut W,
     ) -> Result<LoxValue<'src>, LoxError<RuntimeError>> {
-        if let Some(value) = env.borrow().get(self.var.src) {
-            Ok(value.clone())
+        if let Some(value) = env.borrow().get(self.binding.get(), self.var.src) {
+            Ok(value)
+        } else if let Some(native) = Native::find(self.var.src) {
+            // The name is a builtin, but its capability was not granted.
+            Err(CapabilityDenied(self.var.src.to_string(), native.capability).at(self.line()))
         } else {
             Err(UndefinedVariable(self.var.src.to_string()).at(self.line()))
         }
@@ -0,0 +1,339 @@
+use crate::{
+    expr::ExprAst,
+    mac::tt,
+    statement::{Block, StmtAst},
+    token::Token,
+};
+
+const INDENT: &str = "    ";
+
+/// A comment kept by [`Tokenizer::with_comments`](crate::token::Tokenizer).
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Comment<'src> {
+    pub(crate) line: usize,
+    pub(crate) src: &'src str,
+    /// Whether there is code before the comment on the same line.
+    trailing: bool,
+    /// Index of the code token after the comment, which it is printed
+    /// before.
+    before: usize,
+}
+
+/// Separates comments from the tokens, so that the rest can be parsed as
+/// usual.
+pub(crate) fn split_comments<'src>(
+    tokens: Vec<Token<'src>>,
+) -> (Vec<Token<'src>>, Vec<Comment<'src>>) {
+    let mut code = Vec::new();
+    let mut comments = Vec::new();
+
+    for token in tokens {
+        if token.token_type == tt!("comment") {
+            let trailing = code
+                .last()
+                .is_some_and(|last: &Token| last.line == token.line);
+            comments.push(Comment {
+                line: token.line,
+                src: token.src,
+                trailing,
+                before: code.len(),
+            });
+        } else {
+            code.push(token);
+        }
+    }
+
+    (code, comments)
+}
+
+/// Pretty-printer for statements. Comments are not part of the AST, so each
+/// is anchored to the code token after it, and printed when the printer
+/// reaches that token: at the end of the line of the token before it if it
+/// was there in the source, and on its own line otherwise.
+///
+/// At most one blank line between statements is kept, and the output of
+/// formatting a formatted program is the same program.
+pub(crate) struct Formatter<'src> {
+    comments: Vec<Comment<'src>>,
+    /// Index of the next comment to be printed.
+    next_comment: usize,
+    /// Index of the next code token to be printed. Every token of the
+    /// source is printed, in its order.
+    next_token: usize,
+    out: String,
+    depth: usize,
+    /// Source line where the last printed statement or comment ends, or
+    /// `None` at the start of a block.
+    last_line: Option<usize>,
+}
+
+impl<'src> Formatter<'src> {
+    pub(crate) fn new(comments: Vec<Comment<'src>>) -> Self {
+        Self {
+            comments,
+            next_comment: 0,
+            next_token: 0,
+            out: String::new(),
+            depth: 0,
+            last_line: None,
+        }
+    }
+
+    pub(crate) fn format(mut self, stmts: &[StmtAst<'src>]) -> String {
+        self.stmts(stmts);
+        self.out
+    }
+
+    /// Prints statements, one per line, and the comments up to the next
+    /// token after them.
+    fn stmts(&mut self, stmts: &[StmtAst<'src>]) {
+        for stmt in stmts {
+            self.comments_on_own_lines();
+            self.start_line(stmt.start_line());
+            self.stmt(stmt);
+            self.trailing_comment();
+            self.out.push('\n');
+            self.last_line = Some(stmt.end_line());
+        }
+        self.comments_on_own_lines();
+    }
+
+    /// Prints the comments before the next token, each on its own line.
+    fn comments_on_own_lines(&mut self) {
+        while let Some(&comment) = self.pending_comment() {
+            self.next_comment += 1;
+            self.start_line(comment.line);
+            self.out.push_str(comment.src);
+            self.out.push('\n');
+            self.last_line = Some(comment.line);
+        }
+    }
+
+    /// Appends the comment which follows the last printed token on its line,
+    /// if any.
+    fn trailing_comment(&mut self) {
+        if let Some(&comment) = self.pending_comment().filter(|comment| comment.trailing) {
+            self.next_comment += 1;
+            self.out.push_str("  ");
+            self.out.push_str(comment.src);
+        }
+    }
+
+    /// The next comment, if it comes before the next token.
+    fn pending_comment(&self) -> Option<&Comment<'src>> {
+        self.comments
+            .get(self.next_comment)
+            .filter(|comment| comment.before <= self.next_token)
+    }
+
+    /// Indents a new line, after a blank line if there was one in the source.
+    fn start_line(&mut self, line: usize) {
+        if self.last_line.is_some_and(|last| line > last + 1) {
+            self.out.push('\n');
+        }
+        self.out.push_str(&INDENT.repeat(self.depth));
+    }
+
+    /// Prints the next token within a statement, as continuing it.
+    fn token(&mut self, text: &str) {
+        self.token_at(text, self.depth + 1);
+    }
+
+    /// Prints the next token, after the comments before it. A comment ends
+    /// its line, so the token then starts a new one, indented by `indent`.
+    fn token_at(&mut self, text: &str, indent: usize) {
+        if self.pending_comment().is_some() {
+            self.out.truncate(self.out.trim_end_matches(' ').len());
+            self.trailing_comment();
+            while let Some(&comment) = self.pending_comment() {
+                self.next_comment += 1;
+                self.break_line(indent);
+                self.out.push_str(comment.src);
+            }
+            self.break_line(indent);
+        }
+        self.out.push_str(text);
+        self.next_token += 1;
+    }
+
+    /// Starts a new line, unless at the start of one, indented by `indent`.
+    fn break_line(&mut self, indent: usize) {
+        if !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&INDENT.repeat(indent));
+    }
+
+    fn stmt(&mut self, stmt: &StmtAst<'src>) {
+        match stmt {
+            StmtAst::Expression(v) => {
+                self.expr(&v.expr);
+                self.token(";");
+            }
+            StmtAst::Print(v) => {
+                self.token("print");
+                self.out.push(' ');
+                self.expr(&v.expr);
+                self.token(";");
+            }
+            StmtAst::VarDecl(v) => {
+                self.token("var");
+                self.out.push(' ');
+                self.expr(&v.var);
+                if let Some(value) = v.value.as_ref() {
+                    self.out.push(' ');
+                    self.token("=");
+                    self.out.push(' ');
+                    self.expr(value);
+                }
+                self.token(";");
+            }
+            StmtAst::Block(v) => self.block(v),
+            StmtAst::If(v) => {
+                self.token("if");
+                self.out.push(' ');
+                self.token("(");
+                self.expr(&v.condition);
+                self.token(")");
+                self.out.push(' ');
+                self.stmt(&v.body);
+
+                if let Some(else_body) = v.else_body.as_ref() {
+                    if matches!(*v.body, StmtAst::Block(_)) {
+                        self.out.push(' ');
+                    } else {
+                        self.trailing_comment();
+                        self.out.push('\n');
+                        self.out.push_str(&INDENT.repeat(self.depth));
+                    }
+                    self.token_at("else", self.depth);
+                    self.out.push(' ');
+                    self.stmt(else_body);
+                }
+            }
+            StmtAst::While(v) => {
+                self.token("while");
+                self.out.push(' ');
+                self.token("(");
+                self.expr(&v.condition);
+                self.token(")");
+                self.out.push(' ');
+                self.stmt(&v.body);
+            }
+            StmtAst::For(v) => {
+                self.token("for");
+                self.out.push(' ');
+                self.token("(");
+                match v.initializer.as_ref() {
+                    Some(initializer) => self.stmt(initializer),
+                    None => self.token(";"),
+                }
+                if let Some(condition) = v.condition.as_ref() {
+                    self.out.push(' ');
+                    self.expr(condition);
+                }
+                self.token(";");
+                if let Some(increment) = v.increment.as_ref() {
+                    self.out.push(' ');
+                    self.expr(increment);
+                }
+                self.token(")");
+                self.out.push(' ');
+                self.stmt(&v.body);
+            }
+            StmtAst::FunctionDef(v) => {
+                self.token("fun");
+                self.out.push(' ');
+                self.token(v.name.src);
+                self.token("(");
+                for (i, argument) in v.arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.token(",");
+                        self.out.push(' ');
+                    }
+                    self.token(argument.src);
+                }
+                self.token(")");
+                self.out.push(' ');
+                self.braces(&v.body);
+            }
+            StmtAst::Return(v) => {
+                self.token("return");
+                if let Some(expr) = v.expr.as_ref() {
+                    self.out.push(' ');
+                    self.expr(expr);
+                }
+                self.token(";");
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block<'src>) {
+        self.braces(&block.inner);
+    }
+
+    /// Prints `{}`, or statements in braces.
+    fn braces(&mut self, stmts: &[StmtAst<'src>]) {
+        self.token_at("{", self.depth);
+        if stmts.is_empty() && self.pending_comment().is_none() {
+            self.token("}");
+            return;
+        }
+
+        self.trailing_comment();
+        self.out.push('\n');
+        self.depth += 1;
+        self.last_line = None;
+        self.stmts(stmts);
+        self.depth -= 1;
+        self.out.push_str(&INDENT.repeat(self.depth));
+        self.token_at("}", self.depth);
+    }
+
+    fn expr(&mut self, expr: &ExprAst<'src>) {
+        match expr {
+            ExprAst::Assign(v) => {
+                self.expr(v.assignee);
+                self.out.push(' ');
+                self.token("=");
+                self.out.push(' ');
+                self.expr(v.value);
+            }
+            ExprAst::Binary(v) => {
+                self.expr(v.left);
+                self.out.push(' ');
+                self.token(&v.op.to_string());
+                self.out.push(' ');
+                self.expr(v.right);
+            }
+            ExprAst::FieldCall(v) => {
+                self.expr(v.object);
+                self.token(".");
+                self.token(&v.field);
+            }
+            ExprAst::FunctionCall(v) => {
+                self.expr(v.callee);
+                self.token("(");
+                for (i, argument) in v.arguments.iter().enumerate() {
+                    if i > 0 {
+                        self.token(",");
+                        self.out.push(' ');
+                    }
+                    self.expr(argument);
+                }
+                self.token(")");
+            }
+            ExprAst::Grouping(v) => {
+                self.token("(");
+                self.expr(v.inner);
+                self.token(")");
+            }
+            ExprAst::LiteralExpr(v) => self.token(v.token.src),
+            ExprAst::Unary(v) => {
+                self.token(&v.op.to_string());
+                self.expr(v.right);
+            }
+            ExprAst::Variable(v) => self.token(v.var.src),
+        }
+    }
+}
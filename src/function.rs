@@ -1,25 +1,17 @@
-use std::{
-    cell::RefCell,
-    fmt,
-    io::Write,
-    rc::Rc,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::{cell::RefCell, fmt, io::Write, rc::Rc};
 
 use crate::{
     env::{
-        Evaluatable, Runnable,
+        Binding, Runnable,
         RuntimeError::{self, *},
     },
-    literal::{Literal, LoxValue, Number},
-    statement::{FunctionDef, Return, StmtAst},
+    literal::LoxValue,
+    native,
+    statement::FunctionDef,
     Env,
 };
 
 pub(crate) trait Callable<'a> {
-    // Required methods
-    fn argument_names(&self) -> Vec<&str>;
-
     /// call and get the result.
     fn call<W: Write>(
         &self,
@@ -33,6 +25,8 @@ pub(crate) trait Callable<'a> {
 pub(crate) struct RustFunction<'a> {
     pub(crate) name: &'a str,
     pub(crate) arguments: Vec<&'a str>,
+    /// How many of the trailing arguments can be omitted.
+    pub(crate) optional: usize,
 }
 
 impl fmt::Display for RustFunction<'_> {
@@ -41,61 +35,39 @@ impl fmt::Display for RustFunction<'_> {
     }
 }
 
-pub(crate) fn rust_clock_function<'a>() -> RustFunction<'a> {
-    return RustFunction {
-        name: "clock",
-        arguments: vec![],
-    };
-}
-
 impl<'a> Callable<'a> for RustFunction<'_> {
-    fn argument_names(&self) -> Vec<&str> {
-        self.arguments.to_vec()
-    }
-
     fn call<W: Write>(
         &self,
         arguments: Vec<LoxValue<'a>>,
-        _: Rc<RefCell<Env<'a>>>,
+        env: Rc<RefCell<Env<'a>>>,
         _: &mut W,
     ) -> Result<LoxValue<'a>, RuntimeError> {
-        if arguments.len() != self.arguments.len() {
+        let required = self.arguments.len() - self.optional;
+        if !(required..=self.arguments.len()).contains(&arguments.len()) {
             return Err(InvalidNumberOfArguments);
         }
 
-        match self.name {
-            "clock" => Ok(clock()),
-            rest => unreachable!("there are no builtin function named {rest}"),
-        }
+        let runtime = env.borrow().runtime.clone();
+        native::call(self.name, arguments, &runtime)
     }
 }
 
-fn clock<'a>() -> LoxValue<'a> {
-    let elapsed_secs_from_epoch = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as f64;
-
-    LoxValue::Literal(Literal::Number(Number(elapsed_secs_from_epoch)))
-}
-
 #[derive(Clone)]
 pub(crate) struct LoxFunction<'src> {
-    pub(crate) def: FunctionDef<'src>,
+    pub(crate) def: Rc<FunctionDef<'src>>,
     pub(crate) closure: Rc<RefCell<Env<'src>>>,
 }
 
+/// Like every Lox object, functions are equal only to themselves: a
+/// declaration run again, e.g. in a loop, defines another function, as it
+/// closes over another environment.
 impl PartialEq for LoxFunction<'_> {
     fn eq(&self, other: &Self) -> bool {
-        self.def == other.def
+        Rc::ptr_eq(&self.def, &other.def) && Rc::ptr_eq(&self.closure, &other.closure)
     }
 }
 
 impl<'src> Callable<'src> for LoxFunction<'src> {
-    fn argument_names(&self) -> Vec<&str> {
-        self.def.arguments.iter().map(|s| s.as_str()).collect()
-    }
-
     fn call<W: Write>(
         &self,
         arguments: Vec<LoxValue<'src>>,
@@ -108,32 +80,19 @@ impl<'src> Callable<'src> for LoxFunction<'src> {
 
         // Initialize scope environment.
         let scope_env = Env::from_parent(self.closure.clone());
-        for (name, value) in self.argument_names().into_iter().zip(arguments.into_iter()) {
-            scope_env.borrow_mut().set(name, value);
+        // Arguments take the first slots, as the resolver numbers them.
+        for (slot, (name, value)) in self.def.arguments.iter().zip(arguments).enumerate() {
+            let binding = Binding::Local { depth: 0, slot };
+            scope_env.borrow_mut().define(binding, name.src, value);
         }
 
         for stmt in self.def.body.iter() {
-            match stmt {
-                StmtAst::Return(Return { expr, .. }) => {
-                    let value = expr
-                        .as_ref()
-                        .map(|e| e.eval(scope_env, stdout))
-                        .transpose()
-                        // when called, error line should be not from the function body
-                        .map_err(|err| err.kind)?
-                        .unwrap_or_default();
-
-                    return Ok(value);
-                }
-                rest => {
-                    // when called, error line should be not from the function body
-                    if let Some(value) = rest
-                        .run(scope_env.clone(), stdout)
-                        .map_err(|err| err.kind)?
-                    {
-                        return Ok(value);
-                    }
-                }
+            // when called, error line should be not from the function body
+            if let Some(value) = stmt
+                .run(scope_env.clone(), stdout)
+                .map_err(|err| err.kind)?
+            {
+                return Ok(value);
             }
         }
 
@@ -150,6 +109,6 @@ impl fmt::Debug for LoxFunction<'_> {
 
 impl fmt::Display for LoxFunction<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "<fn {}>", self.def.name)
+        write!(f, "<fn {}>", self.def.name.src)
     }
 }
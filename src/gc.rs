@@ -0,0 +1,276 @@
+//! Collector of the reference cycles that `Rc` cannot free, e.g. a function
+//! stored in the environment it closes over.
+//!
+//! Every environment, list and map is tracked by a weak reference. A
+//! collection subtracts the references the tracked objects hold to each
+//! other from their reference counts: what is left is held from outside,
+//! e.g. by a variable of the interpreter. Objects not reachable from those
+//! are only held by each other, and are cleared to break their cycles.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::Write,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    env::Env,
+    literal::{Entry, List, LoxValue, Map},
+};
+
+/// Objects tracked since the last collection, before another is made. It
+/// grows with the objects still alive after a collection.
+const MIN_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct GcStats {
+    pub(crate) collections: usize,
+    /// Objects freed by all the collections.
+    pub(crate) freed: usize,
+    /// Objects alive after the last collection.
+    pub(crate) live: usize,
+}
+
+pub(crate) struct Heap<'src> {
+    objects: RefCell<Vec<Tracked<'src>>>,
+    /// Objects tracked since the last collection.
+    allocated: Cell<usize>,
+    threshold: Cell<usize>,
+    stats: Cell<GcStats>,
+    /// A line for each collection, if `--gc-log` is given.
+    log: Option<RefCell<Vec<String>>>,
+}
+
+enum Tracked<'src> {
+    Env(Weak<RefCell<Env<'src>>>),
+    List(Weak<RefCell<Vec<LoxValue<'src>>>>),
+    Map(Weak<RefCell<Vec<Entry<'src>>>>),
+}
+
+/// A tracked object, held during a collection.
+enum Object<'src> {
+    Env(Rc<RefCell<Env<'src>>>),
+    List(Rc<RefCell<Vec<LoxValue<'src>>>>),
+    Map(Rc<RefCell<Vec<Entry<'src>>>>),
+}
+
+impl Default for Heap<'_> {
+    fn default() -> Self {
+        Self {
+            objects: RefCell::default(),
+            allocated: Cell::new(0),
+            threshold: Cell::new(MIN_THRESHOLD),
+            stats: Cell::default(),
+            log: None,
+        }
+    }
+}
+
+impl<'src> Heap<'src> {
+    pub fn with_log(mut self) -> Self {
+        self.log = Some(RefCell::default());
+        self
+    }
+
+    pub fn env(&self, env: &Rc<RefCell<Env<'src>>>) {
+        self.track(Tracked::Env(Rc::downgrade(env)));
+    }
+
+    pub fn list(&self, items: Vec<LoxValue<'src>>) -> List<'src> {
+        let list = List::new(items);
+        self.track(Tracked::List(Rc::downgrade(&list.0)));
+        list
+    }
+
+    pub fn map(&self, entries: Vec<Entry<'src>>) -> Map<'src> {
+        let map = Map::new(entries);
+        self.track(Tracked::Map(Rc::downgrade(&map.0)));
+        map
+    }
+
+    pub fn stats(&self) -> GcStats {
+        self.stats.get()
+    }
+
+    /// Writes the lines logged since the last call.
+    pub fn write_log(&self, out: &mut impl Write) {
+        if let Some(log) = &self.log {
+            for line in log.borrow_mut().drain(..) {
+                writeln!(out, "{line}").unwrap();
+            }
+        }
+    }
+
+    fn track(&self, tracked: Tracked<'src>) {
+        self.objects.borrow_mut().push(tracked);
+        self.allocated.set(self.allocated.get() + 1);
+        if self.allocated.get() >= self.threshold.get() {
+            self.collect();
+        }
+    }
+
+    pub fn collect(&self) {
+        let objects: Vec<_> = self
+            .objects
+            .take()
+            .iter()
+            .filter_map(Tracked::upgrade)
+            .collect();
+        let index: HashMap<_, _> = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| (object.ptr(), i))
+            .collect();
+
+        // The count held by `objects` is not a reference from outside.
+        let mut outside: Vec<_> = objects.iter().map(|o| o.strong_count() - 1).collect();
+        // None for an object borrowed by the interpreter, which is kept
+        // along with everything it may hold.
+        let children: Vec<Option<Vec<usize>>> = objects
+            .iter()
+            .map(|object| {
+                let children = object.children()?;
+                Some(
+                    children
+                        .iter()
+                        .filter_map(|ptr| index.get(ptr).copied())
+                        .collect(),
+                )
+            })
+            .collect();
+        for child in children.iter().flatten().flatten() {
+            outside[*child] = outside[*child].saturating_sub(1);
+        }
+
+        let mut alive = vec![false; objects.len()];
+        let mut stack: Vec<_> = (0..objects.len())
+            .filter(|i| outside[*i] > 0 || children[*i].is_none())
+            .collect();
+        while let Some(i) = stack.pop() {
+            if !alive[i] {
+                alive[i] = true;
+                stack.extend(children[i].iter().flatten());
+            }
+        }
+
+        let mut tracked = Vec::new();
+        let mut freed = 0;
+        for (object, alive) in objects.iter().zip(&alive) {
+            if *alive {
+                tracked.push(object.downgrade());
+            } else {
+                object.clear();
+                freed += 1;
+            }
+        }
+        let live = tracked.len();
+        self.objects.borrow_mut().extend(tracked);
+        // The cleared objects are freed here, as nothing else holds them.
+        drop(objects);
+
+        let stats = self.stats.get();
+        let stats = GcStats {
+            collections: stats.collections + 1,
+            freed: stats.freed + freed,
+            live,
+        };
+        self.stats.set(stats);
+        self.allocated.set(0);
+        self.threshold.set(MIN_THRESHOLD.max(live));
+        if let Some(log) = &self.log {
+            log.borrow_mut().push(format!(
+                "[gc] collection {}: freed {freed} of {} objects",
+                stats.collections,
+                freed + live
+            ));
+        }
+    }
+}
+
+impl<'src> Tracked<'src> {
+    fn upgrade(&self) -> Option<Object<'src>> {
+        match self {
+            Self::Env(env) => env.upgrade().map(Object::Env),
+            Self::List(list) => list.upgrade().map(Object::List),
+            Self::Map(map) => map.upgrade().map(Object::Map),
+        }
+    }
+}
+
+impl<'src> Object<'src> {
+    fn downgrade(&self) -> Tracked<'src> {
+        match self {
+            Self::Env(env) => Tracked::Env(Rc::downgrade(env)),
+            Self::List(list) => Tracked::List(Rc::downgrade(list)),
+            Self::Map(map) => Tracked::Map(Rc::downgrade(map)),
+        }
+    }
+
+    fn ptr(&self) -> *const () {
+        match self {
+            Self::Env(env) => Rc::as_ptr(env) as *const (),
+            Self::List(list) => Rc::as_ptr(list) as *const (),
+            Self::Map(map) => Rc::as_ptr(map) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::Env(env) => Rc::strong_count(env),
+            Self::List(list) => Rc::strong_count(list),
+            Self::Map(map) => Rc::strong_count(map),
+        }
+    }
+
+    /// Objects this one holds a reference to, one for each reference.
+    fn children(&self) -> Option<Vec<*const ()>> {
+        let mut children = Vec::new();
+        match self {
+            Self::Env(env) => {
+                let env = env.try_borrow().ok()?;
+                if let Some(parent) = &env.parent {
+                    children.push(Rc::as_ptr(parent) as *const ());
+                }
+                env.slots
+                    .iter()
+                    .for_each(|(_, value)| push_child(value, &mut children));
+            }
+            Self::List(list) => list
+                .try_borrow()
+                .ok()?
+                .iter()
+                .for_each(|value| push_child(value, &mut children)),
+            Self::Map(map) => map
+                .try_borrow()
+                .ok()?
+                .iter()
+                .for_each(|(_, value)| push_child(value, &mut children)),
+        }
+        Some(children)
+    }
+
+    /// Drops everything the object holds, which breaks its cycles. The
+    /// other objects of the cycles are still held by the collection.
+    fn clear(&self) {
+        match self {
+            Self::Env(env) => {
+                let mut env = env.borrow_mut();
+                env.parent = None;
+                env.slots.clear();
+            }
+            Self::List(list) => list.borrow_mut().clear(),
+            Self::Map(map) => map.borrow_mut().clear(),
+        }
+    }
+}
+
+fn push_child(value: &LoxValue, children: &mut Vec<*const ()>) {
+    let ptr = match value {
+        LoxValue::LoxFunction(function) => Rc::as_ptr(&function.closure) as *const (),
+        LoxValue::List(list) => Rc::as_ptr(&list.0) as *const (),
+        LoxValue::Map(map) => Rc::as_ptr(&map.0) as *const (),
+        LoxValue::Literal(_) | LoxValue::RustFunction(_) => return,
+    };
+    children.push(ptr);
+}
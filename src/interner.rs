@@ -0,0 +1,38 @@
+//! One shared copy of each identifier and string constant of a run.
+
+use std::{collections::HashMap, rc::Rc};
+
+/// Index of an interned string. Globals are stored by the symbol of their
+/// name, so that reading one does not hash the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Symbol(pub(crate) usize);
+
+#[derive(Default)]
+pub(crate) struct Interner {
+    symbols: HashMap<Rc<str>, Symbol>,
+    strings: Vec<Rc<str>>,
+}
+
+impl Interner {
+    /// The same symbol for the same string, however many times it is given.
+    /// The string is copied only the first time.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(s) {
+            return *symbol;
+        }
+        let symbol = Symbol(self.strings.len());
+        let s: Rc<str> = s.into();
+        self.symbols.insert(s.clone(), symbol);
+        self.strings.push(s);
+        symbol
+    }
+
+    pub fn string(&self, symbol: Symbol) -> Rc<str> {
+        self.strings[symbol.0].clone()
+    }
+
+    /// Every string, in the order of their symbols.
+    pub fn strings(&self) -> impl Iterator<Item = &str> {
+        self.strings.iter().map(|s| &**s)
+    }
+}
@@ -0,0 +1,416 @@
+use std::fmt::{self, Write};
+
+/// JSON document. Object keys keep the order they were written in.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Malformed JSON, with the byte offset where it was noticed.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message} at byte {offset}")]
+pub(crate) struct JsonError {
+    pub(crate) offset: usize,
+    pub(crate) message: &'static str,
+}
+
+/// Deeper documents are rejected rather than overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+impl Json {
+    pub fn parse(src: &str) -> Result<Json, JsonError> {
+        let mut parser = JsonParser { src, pos: 0 };
+        let json = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.pos < src.len() {
+            return Err(parser.error("Unexpected trailing characters"));
+        }
+        Ok(json)
+    }
+
+    /// Object from fields, e.g. `Json::object([("line", 1.into())])`.
+    pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Value of the field, if this is an object which has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Serializes with `indent` spaces per level, or on a single line if
+    /// `indent` is zero.
+    pub fn to_string_indented(&self, indent: usize) -> String {
+        let mut out = String::new();
+        self.write_indented(&mut out, indent, 0).unwrap();
+        out
+    }
+
+    fn write_indented(&self, out: &mut String, indent: usize, level: usize) -> fmt::Result {
+        let newline = |out: &mut String, level: usize| {
+            if indent > 0 {
+                out.push('\n');
+                out.push_str(&" ".repeat(indent * level));
+            }
+        };
+        let separator = if indent > 0 { ": " } else { ":" };
+
+        match self {
+            Self::Array(items) if !items.is_empty() => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, level + 1);
+                    item.write_indented(out, indent, level + 1)?;
+                }
+                newline(out, level);
+                out.push(']');
+            }
+            Self::Object(fields) if !fields.is_empty() => {
+                out.push('{');
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    newline(out, level + 1);
+                    write_string(out, key)?;
+                    out.push_str(separator);
+                    value.write_indented(out, indent, level + 1)?;
+                }
+                newline(out, level);
+                out.push('}');
+            }
+            Self::Array(_) => out.push_str("[]"),
+            Self::Object(_) => out.push_str("{}"),
+            Self::Null => out.push_str("null"),
+            Self::Bool(b) => write!(out, "{b}")?,
+            // JSON has no NaN or infinity, so they become null like
+            // JSON.stringify in javascript does.
+            Self::Number(n) if !n.is_finite() => out.push_str("null"),
+            Self::Number(n) => write!(out, "{n}")?,
+            Self::String(s) => write_string(out, s)?,
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(value: Vec<Json>) -> Self {
+        Self::Array(value)
+    }
+}
+
+/// Compact form, on a single line.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_indented(0))
+    }
+}
+
+fn write_string(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+struct JsonParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn parse_value(&mut self, depth: usize) -> Result<Json, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Too deeply nested"));
+        }
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(b't') => self.parse_keyword("true", Json::Bool(true)),
+            Some(b'f') => self.parse_keyword("false", Json::Bool(false)),
+            Some(b'n') => self.parse_keyword("null", Json::Null),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.pos += 1; // Consume '{'.
+        let mut fields = Vec::new();
+
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected string key"));
+            }
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            if !self.eat(b':') {
+                return Err(self.error("Expected ':'"));
+            }
+            let value = self.parse_value(depth + 1)?;
+            fields.push((key, value));
+
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return Ok(Json::Object(fields));
+            } else if !self.eat(b',') {
+                return Err(self.error("Expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Json, JsonError> {
+        self.pos += 1; // Consume '['.
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return Ok(Json::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(Json::Array(items));
+            } else if !self.eat(b',') {
+                return Err(self.error("Expected ',' or ']'"));
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1; // Consume '"'.
+        let mut s = String::new();
+
+        loop {
+            let rest = &self.src[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("Unterminated string"));
+            };
+
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    s.push(self.parse_escape()?);
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("Control character in string")),
+                c => {
+                    self.pos += c.len_utf8();
+                    s.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parses the escape sequence after '\'.
+    fn parse_escape(&mut self) -> Result<char, JsonError> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                return self.parse_unicode_escape();
+            }
+            _ => return Err(self.error("Invalid escape sequence")),
+        };
+        self.pos += 1;
+        Ok(escaped)
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.parse_hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+
+        // A high surrogate must be followed by an escaped low surrogate.
+        if !self.src[self.pos..].starts_with("\\u") {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        self.pos += 2;
+        let low = self.parse_hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("Unpaired surrogate"));
+        }
+
+        let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let hex = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+
+        self.pos += 4;
+        Ok(u32::from_str_radix(hex, 16).unwrap())
+    }
+
+    fn parse_number(&mut self) -> Result<Json, JsonError> {
+        let start = self.pos;
+        self.eat(b'-');
+
+        // Leading zeros are not allowed, e.g. 01.
+        if !self.eat(b'0') && !self.eat_digits() {
+            return Err(self.error("Expected digit"));
+        }
+        if self.eat(b'.') && !self.eat_digits() {
+            return Err(self.error("Expected digit"));
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            let _ = self.eat(b'+') || self.eat(b'-');
+            if !self.eat_digits() {
+                return Err(self.error("Expected digit"));
+            }
+        }
+
+        let number = self.src[start..self.pos].parse().unwrap();
+        Ok(Json::Number(number))
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, JsonError> {
+        if self.src[self.pos..].starts_with(keyword) {
+            self.pos += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("Unexpected character"))
+        }
+    }
+
+    /// Consumes one or more digits.
+    fn eat_digits(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, message: &'static str) -> JsonError {
+        JsonError {
+            offset: self.pos,
+            message,
+        }
+    }
+}
@@ -1,29 +1,54 @@
+mod cache;
+mod capability;
+mod coverage;
+mod dap;
+mod debugger;
+mod dump;
 mod env;
 mod error;
 mod expr;
+mod format;
 mod function;
+mod gc;
+mod interner;
+mod json;
+mod lint;
 mod literal;
+mod lsp;
 mod mac;
+mod native;
+mod optimizer;
+mod profiler;
+mod random;
+mod resolver;
+mod runtime;
+mod sexpr;
 mod statement;
 mod token;
+mod tracer;
 
-use std::{io::Write, process::ExitCode};
+use std::{
+    io::{BufRead, Write},
+    process::ExitCode,
+    rc::Rc,
+};
 
+pub use self::capability::{Capabilities, Capability};
 use self::{
-    env::{Env, Evaluatable, Runnable},
-    error::{IntoLoxError, LoxResult},
+    env::{Env, Evaluatable, Runnable, RuntimeError},
+    error::{IntoLoxError, LoxError, LoxResult},
+    expr::ExprArena,
+    literal::Literal,
     mac::rc_rc,
+    runtime::Runtime,
+    statement::StmtAst,
     token::TokenStream,
 };
 
-/// tokenize without allowing error.
-macro_rules! tokenize {
+/// tokenize with comments, without allowing error.
+macro_rules! tokenize_with_comments {
     ($src:expr, $err_buf:expr) => {
-        match token::Tokenizer::new($src)
-            .tokenize()
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>()
-        {
+        match token::Tokenizer::with_comments($src).collect::<Result<Vec<_>, _>>() {
             Ok(tokens) => tokens,
             Err(err) => {
                 writeln!($err_buf, "{err}").unwrap();
@@ -33,30 +58,48 @@ macro_rules! tokenize {
     };
 }
 
+/// Reports the first error of tokenizing the rest of the stream, which
+/// comes before the result of the parser.
+macro_rules! finish_stream {
+    ($stream:expr, $err_buf:expr) => {
+        if let Err(err) = $stream.finish() {
+            writeln!($err_buf, "{err}").unwrap();
+            // Into `ExitCode`, or the `u8` of `run`.
+            return err.kind.exit_status().into();
+        }
+    };
+}
+
 /// parse expression without allowing error.
 macro_rules! expr_parse {
-    ($stream:expr, $err_buf:expr) => {
-        match expr::ExprParser::new(&mut $stream).parse_with_line() {
+    ($stream:expr, $arena:expr, $err_buf:expr) => {{
+        let mut stream = $stream;
+        let parsed = expr::ExprParser::new(&mut stream, $arena).parse_with_line();
+        finish_stream!(stream, $err_buf);
+        match parsed {
             Ok(ast) => ast,
             Err(err) => {
                 writeln!($err_buf, "{err}").unwrap();
                 return err.kind.exit_code();
             }
         }
-    };
+    }};
 }
 
 /// parse statements without allowing error.
 macro_rules! stmt_parse {
-    ($stream:expr, $err_buf:expr) => {
-        match statement::StmtParser::new(&mut $stream).parse_all() {
+    ($stream:expr, $arena:expr, $err_buf:expr) => {{
+        let mut stream = $stream;
+        let parsed = statement::StmtParser::new(&mut stream, $arena).parse_all();
+        finish_stream!(stream, $err_buf);
+        match parsed {
             Ok(stmts) => stmts,
             Err(err) => {
                 writeln!($err_buf, "{err}").unwrap();
-                return err.kind.exit_code();
+                return err.kind.exit_status().into();
             }
         }
-    };
+    }};
 }
 
 /// Entry point for 'tokenize' command.
@@ -66,9 +109,7 @@ pub fn lox_tokenize<W1: Write, W2: Write>(
     err_buf: &mut W2,
 ) -> ExitCode {
     let mut exit_code = ExitCode::SUCCESS;
-    let tokens = token::Tokenizer::new(src).tokenize();
-
-    for token in tokens {
+    for token in token::Tokenizer::new(src) {
         if let Err(e) = token.write_to_buffer(ok_buf, err_buf) {
             exit_code = e;
         }
@@ -77,21 +118,138 @@ pub fn lox_tokenize<W1: Write, W2: Write>(
     exit_code
 }
 
+/// Options for the 'parse' command.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Print the tree as '-O' makes it for 'run'.
+    pub optimize: bool,
+}
+
 /// Entry point for 'parse' command.
 pub fn lox_parse<W1: Write, W2: Write>(src: &str, ok_buf: &mut W1, err_buf: &mut W2) -> ExitCode {
-    // Try tokenizing. If fails, don't parse.
-    let tokens = tokenize!(src, err_buf);
+    lox_parse_with(src, &ParseOptions::default(), ok_buf, err_buf)
+}
 
-    let mut stream = TokenStream::new(&tokens);
+/// Entry point for 'parse' command, with explicit options.
+pub fn lox_parse_with<W1: Write, W2: Write>(
+    src: &str,
+    options: &ParseOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let mut parsed = expr_parse!(
+        TokenStream::new(token::Tokenizer::new(src)),
+        &arena,
+        err_buf
+    );
+    if options.optimize {
+        parsed = optimizer::expr(&parsed, &arena);
+    }
+    writeln!(ok_buf, "{parsed}").unwrap();
+    ExitCode::SUCCESS
+}
 
-    if let Err(exit_code) = expr::ExprParser::new(&mut stream)
-        .parse_with_line()
-        .write_to_buffer(ok_buf, err_buf)
-    {
-        exit_code
-    } else {
-        ExitCode::SUCCESS
+/// Entry point for 'parse --program'. Unlike 'parse', the whole program is
+/// parsed as statements, which are printed one per line.
+pub fn lox_parse_program<W1: Write, W2: Write>(
+    src: &str,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    lox_parse_program_with(src, &ParseOptions::default(), ok_buf, err_buf)
+}
+
+/// Entry point for 'parse --program', with explicit options.
+pub fn lox_parse_program_with<W1: Write, W2: Write>(
+    src: &str,
+    options: &ParseOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let mut stmts = stmt_parse!(
+        TokenStream::new(token::Tokenizer::new(src)),
+        &arena,
+        err_buf
+    );
+    if options.optimize {
+        stmts = optimizer::optimize(stmts, &arena);
+    }
+    for stmt in stmts {
+        writeln!(ok_buf, "{}", sexpr::stmt(&stmt)).unwrap();
     }
+
+    ExitCode::SUCCESS
+}
+
+/// Entry point for 'tokenize --format json'. Prints a single object with
+/// the tokens, and the errors of the characters which could not be
+/// tokenized.
+pub fn lox_tokenize_json<W: Write>(src: &str, ok_buf: &mut W) -> ExitCode {
+    let mut exit_code = ExitCode::SUCCESS;
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for token in token::Tokenizer::new(src) {
+        match token {
+            Ok(token) => tokens.push(dump::token(&token)),
+            Err(err) => {
+                errors.push(dump::error(err.kind.code(), &err));
+                exit_code = err.kind.exit_code();
+            }
+        }
+    }
+
+    let output = json::Json::object([("tokens", tokens.into()), ("errors", errors.into())]);
+    writeln!(ok_buf, "{output}").unwrap();
+    exit_code
+}
+
+/// Entry point for 'parse --format json'. Unlike 'parse', the whole program
+/// is parsed as statements. Prints a single object with the statements, or
+/// null if there is any error.
+pub fn lox_parse_json<W: Write>(src: &str, ok_buf: &mut W) -> ExitCode {
+    lox_parse_json_with(src, &ParseOptions::default(), ok_buf)
+}
+
+/// Entry point for 'parse --format json', with explicit options.
+pub fn lox_parse_json_with<W: Write>(
+    src: &str,
+    options: &ParseOptions,
+    ok_buf: &mut W,
+) -> ExitCode {
+    let mut exit_code = ExitCode::SUCCESS;
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for token in token::Tokenizer::new(src) {
+        match token {
+            Ok(token) => tokens.push(token),
+            Err(err) => {
+                errors.push(dump::error(err.kind.code(), &err));
+                exit_code = err.kind.exit_code();
+            }
+        }
+    }
+
+    let mut stmts = json::Json::Null;
+    let arena = ExprArena::default();
+    if errors.is_empty() {
+        let mut stream = TokenStream::new(tokens.into_iter().map(Ok));
+        match statement::StmtParser::new(&mut stream, &arena).parse_all() {
+            Ok(parsed) if options.optimize => {
+                stmts = dump::stmts(&optimizer::optimize(parsed, &arena))
+            }
+            Ok(parsed) => stmts = dump::stmts(&parsed),
+            Err(err) => {
+                errors.push(dump::error(err.kind.code(), &err));
+                exit_code = err.kind.exit_code();
+            }
+        }
+    }
+
+    let output = json::Json::object([("statements", stmts), ("errors", errors.into())]);
+    writeln!(ok_buf, "{output}").unwrap();
+    exit_code
 }
 
 /// Entry point for 'evaluate' command.
@@ -100,14 +258,16 @@ pub fn lox_evaluate<W1: Write, W2: Write>(
     ok_buf: &mut W1,
     err_buf: &mut W2,
 ) -> ExitCode {
-    let tokens = tokenize!(src, err_buf);
-
-    let mut stream = TokenStream::new(&tokens);
-    let parsed = expr_parse!(stream, err_buf);
+    let arena = ExprArena::default();
+    let parsed = expr_parse!(
+        TokenStream::new(token::Tokenizer::new(src)),
+        &arena,
+        err_buf
+    );
 
     // Since 'evaluate' command doesn't actually print anything while evaluating,
     // we can set env.stdout to be some blank buffer.
-    let empty_env = Env::new();
+    let empty_env = Env::new(Capabilities::default(), Runtime::default());
 
     if let Err(exit_code) = parsed
         .eval(empty_env, ok_buf)
@@ -120,23 +280,378 @@ pub fn lox_evaluate<W1: Write, W2: Write>(
     }
 }
 
+/// Entry point for 'fmt' command. With `check`, nothing is printed, and it
+/// fails if the source is not formatted already.
+pub fn lox_fmt<W1: Write, W2: Write>(
+    src: &str,
+    check: bool,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let tokens = tokenize_with_comments!(src, err_buf);
+    let (tokens, comments) = format::split_comments(tokens);
+    let arena = ExprArena::default();
+    let stmts = stmt_parse!(
+        TokenStream::new(tokens.into_iter().map(Ok)),
+        &arena,
+        err_buf
+    );
+
+    let mut formatted = format::Formatter::new(comments).format(&stmts);
+    // The tokenizer skips the shebang line, so put it back as is.
+    if let Some(shebang) = src.lines().next().filter(|line| line.starts_with("#!")) {
+        formatted.insert_str(0, &format!("{shebang}\n"));
+    }
+
+    if !check {
+        write!(ok_buf, "{formatted}").unwrap();
+        ExitCode::SUCCESS
+    } else if formatted == src.replace("\r\n", "\n") {
+        ExitCode::SUCCESS
+    } else {
+        writeln!(err_buf, "Error: Source is not formatted.").unwrap();
+        ExitCode::FAILURE
+    }
+}
+
+/// Entry point for 'lint' command. Fails if there is any warning.
+pub fn lox_lint<W1: Write, W2: Write>(src: &str, ok_buf: &mut W1, err_buf: &mut W2) -> ExitCode {
+    let tokens = tokenize_with_comments!(src, err_buf);
+    let (tokens, comments) = format::split_comments(tokens);
+    let arena = ExprArena::default();
+    let stmts = stmt_parse!(
+        TokenStream::new(tokens.into_iter().map(Ok)),
+        &arena,
+        err_buf
+    );
+
+    let warnings = lint::Linter::new().lint(&stmts, &comments);
+    for warning in &warnings {
+        writeln!(ok_buf, "{warning}").unwrap();
+    }
+
+    if warnings.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Entry point for 'compile' command. The program is parsed, optimized if
+/// `optimize`, resolved, and written to `out` for [`lox_run_compiled`].
+/// `path` is the file the source was read from, so that a compiled program
+/// older than its source is rejected. It is empty if there is no such file.
+pub fn lox_compile<W1: Write, W2: Write>(
+    src: &str,
+    path: &str,
+    optimize: bool,
+    out: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let mut stmts = stmt_parse!(
+        TokenStream::new(token::Tokenizer::new(src)),
+        &arena,
+        err_buf
+    );
+    if optimize {
+        stmts = optimizer::optimize(stmts, &arena);
+    }
+    let mut interner = interner::Interner::default();
+    resolver::Resolver::resolve(&mut stmts, &mut interner);
+
+    match cache::encode(src, path, &stmts, &interner) {
+        Ok(bytes) => {
+            out.write_all(&bytes).unwrap();
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            writeln!(err_buf, "{err}").unwrap();
+            ExitCode::from(70)
+        }
+    }
+}
+
+/// Entry point for 'lsp' command, which serves the Language Server Protocol
+/// until the client exits.
+pub fn lox_lsp<R: BufRead, W: Write>(input: R, output: W) -> ExitCode {
+    lsp::Server::new(output).run(input)
+}
+
+/// Entry point for 'dap' command, which serves the Debug Adapter Protocol
+/// until the client disconnects.
+pub fn lox_dap<R: BufRead, W: Write>(input: R, output: W) -> ExitCode {
+    dap::serve(input, output)
+}
+
+/// Options for the 'run' command, given by the CLI or an embedding host.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Which natives the script can see.
+    pub capabilities: Capabilities,
+    /// Exposed to the script as the global `args` list.
+    pub args: Vec<String>,
+    /// Seed for the random natives. Without it, every run differs.
+    pub seed: Option<u64>,
+    /// Write a line to stderr for each collection of reference cycles.
+    pub gc_log: bool,
+    /// Fold constants and remove dead branches before running. A compiled
+    /// program is optimized by 'compile' instead.
+    pub optimize: bool,
+}
+
 /// Entry point for 'run' command.
 pub fn lox_run<W1: Write, W2: Write>(src: &str, ok_buf: &mut W1, err_buf: &mut W2) -> ExitCode {
-    let tokens = tokenize!(src, err_buf);
+    lox_run_with(src, &RunOptions::default(), ok_buf, err_buf)
+}
 
-    let mut stream = TokenStream::new(&tokens);
-    let stmts = stmt_parse!(stream, err_buf);
+/// Entry point for 'run' command, with explicit options.
+pub fn lox_run_with<W1: Write, W2: Write>(
+    src: &str,
+    options: &RunOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let runtime = Runtime::new(options.seed);
+    ExitCode::from(run(src, &arena, options, runtime, ok_buf, err_buf))
+}
 
-    let env = Env::new();
-    for stmt in stmts {
-        if let Err(exit_code) = stmt
-            .run(env.clone(), ok_buf)
-            .map(|res| format!("{res:?}"))
-            .write_to_buffer(&mut Vec::new(), err_buf)
-        {
-            return exit_code;
+/// Whether the file was written by 'compile', rather than being source.
+pub fn lox_is_compiled(file: &[u8]) -> bool {
+    cache::is_compiled(file)
+}
+
+/// Entry point for 'run' command, of a program written by 'compile'. It is
+/// rejected if it is of another version, corrupted, or older than its
+/// source. `options.optimize` is not used, as it is given to 'compile'.
+pub fn lox_run_compiled<W1: Write, W2: Write>(
+    compiled: &[u8],
+    options: &RunOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let program = match cache::decode(compiled, &arena) {
+        Ok(program) => program,
+        Err(err) => {
+            writeln!(err_buf, "{err}").unwrap();
+            return ExitCode::from(65);
         }
+    };
+
+    let runtime = Runtime::new(options.seed);
+    // The runtime is new, so that each name gets the symbol it had.
+    for name in program.globals {
+        runtime.interner.borrow_mut().intern(name);
     }
+    ExitCode::from(execute(program.stmts, options, runtime, ok_buf, err_buf))
+}
 
-    ExitCode::SUCCESS
+/// What 'run' reports besides the output of the program. Any of them can
+/// be combined, as each is a hook of the runtime.
+#[derive(Default)]
+pub struct Instruments<'a> {
+    /// Where the time spent in each function is written after the run, as
+    /// a table.
+    pub profile: Option<&'a mut dyn Write>,
+    /// Where the call stacks are written after the run, folded for flame
+    /// graph tools.
+    pub profile_folded: Option<&'a mut dyn Write>,
+    /// Path of the source, and where which of its lines and branches ran is
+    /// written after the run, as LCOV.
+    pub coverage: Option<(&'a str, &'a mut dyn Write)>,
+    /// Where what the program does is written as it runs.
+    pub trace: Option<&'a mut dyn Write>,
+    /// Trace only within the calls of the function of this name.
+    pub trace_filter: Option<&'a str>,
+}
+
+/// Entry point for 'run' with any of '--profile', '--coverage' and
+/// '--trace'.
+pub fn lox_run_instrumented<W1: Write, W2: Write>(
+    src: &str,
+    options: &RunOptions,
+    instruments: Instruments,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let Instruments {
+        profile,
+        profile_folded,
+        coverage,
+        mut trace,
+        trace_filter,
+    } = instruments;
+
+    let arena = ExprArena::default();
+    let mut runtime = Runtime::new(options.seed);
+    let profiler =
+        (profile.is_some() || profile_folded.is_some()).then(|| Rc::new(profiler::Profiler::new()));
+    if let Some(profiler) = &profiler {
+        runtime = runtime.with_hook(profiler.clone());
+    }
+    let coverage =
+        coverage.map(|(path, lcov)| (path, lcov, Rc::new(coverage::Coverage::default())));
+    if let Some((_, _, coverage)) = &coverage {
+        runtime = runtime.with_hook(coverage.clone());
+    }
+    if let Some(trace) = trace.as_mut() {
+        runtime = runtime.with_hook(tracer::Tracer::new(src, trace_filter, trace));
+    }
+    let exit_code = ExitCode::from(run(src, &arena, options, runtime, ok_buf, err_buf));
+
+    if let Some(profiler) = profiler {
+        if let Some(report) = profile {
+            profiler.write_table(report);
+        }
+        if let Some(folded) = profile_folded {
+            profiler.write_folded(folded);
+        }
+    }
+    if let Some((path, lcov, coverage)) = coverage {
+        coverage.write_lcov(path, lcov);
+    }
+    exit_code
+}
+
+/// Entry point for 'run --profile'. After the run, the time spent in each
+/// function is written to `report` as a table, and to `folded` as folded
+/// stacks for flame graph tools if given.
+pub fn lox_profile<W1: Write, W2: Write, W3: Write>(
+    src: &str,
+    options: &RunOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+    report: &mut W3,
+    folded: Option<&mut dyn Write>,
+) -> ExitCode {
+    let instruments = Instruments {
+        profile: Some(report),
+        profile_folded: folded.map(|folded| folded as &mut dyn Write),
+        ..Default::default()
+    };
+    lox_run_instrumented(src, options, instruments, ok_buf, err_buf)
+}
+
+/// Entry point for 'run --coverage'. After the run, which lines and branches
+/// of the source at `path` ran is written to `lcov`, as LCOV.
+pub fn lox_coverage<W1: Write, W2: Write, W3: Write>(
+    src: &str,
+    path: &str,
+    options: &RunOptions,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+    lcov: &mut W3,
+) -> ExitCode {
+    let instruments = Instruments {
+        coverage: Some((path, lcov)),
+        ..Default::default()
+    };
+    lox_run_instrumented(src, options, instruments, ok_buf, err_buf)
+}
+
+/// Entry point for 'run --trace'. What the program does is written to
+/// `trace` as it runs, only within the calls of the function named `filter`
+/// if given.
+pub fn lox_trace<W1: Write, W2: Write, W3: Write>(
+    src: &str,
+    options: &RunOptions,
+    filter: Option<&str>,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+    trace: &mut W3,
+) -> ExitCode {
+    let instruments = Instruments {
+        trace: Some(trace),
+        trace_filter: filter,
+        ..Default::default()
+    };
+    lox_run_instrumented(src, options, instruments, ok_buf, err_buf)
+}
+
+/// Entry point for 'debug' command. Commands are read from `input`, and the
+/// prompts are written to `ok_buf` along with the output of the program.
+pub fn lox_debug<R: BufRead, W1: Write, W2: Write>(
+    src: &str,
+    options: &RunOptions,
+    input: R,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> ExitCode {
+    let arena = ExprArena::default();
+    let debugger = debugger::Debugger::new(src, &arena, input);
+    let runtime = Runtime::new(options.seed).with_hook(debugger);
+    ExitCode::from(run(src, &arena, options, runtime, ok_buf, err_buf))
+}
+
+/// Runs the program. The runtime is given by the caller, as its hooks may
+/// keep values of the program, which borrow the source. So is the arena of
+/// the expressions, which has to outlive them.
+fn run<'src, W1: Write, W2: Write>(
+    src: &'src str,
+    arena: &'src ExprArena<'src>,
+    options: &RunOptions,
+    runtime: Runtime<'src>,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> u8 {
+    let mut stmts = stmt_parse!(TokenStream::new(token::Tokenizer::new(src)), arena, err_buf);
+    if options.optimize {
+        stmts = optimizer::optimize(stmts, arena);
+    }
+    resolver::Resolver::resolve(&mut stmts, &mut runtime.interner.borrow_mut());
+    execute(stmts, options, runtime, ok_buf, err_buf)
+}
+
+/// Runs the statements, resolved with the interner of the runtime.
+fn execute<'src, W1: Write, W2: Write>(
+    stmts: Vec<StmtAst<'src>>,
+    options: &RunOptions,
+    runtime: Runtime<'src>,
+    ok_buf: &mut W1,
+    err_buf: &mut W2,
+) -> u8 {
+    runtime.start(&stmts);
+    let runtime = match options.gc_log {
+        true => runtime.with_gc_log(),
+        false => runtime,
+    };
+    let env = Env::new(options.capabilities, runtime);
+    let runtime = env.borrow().runtime.clone();
+    let args = options
+        .args
+        .iter()
+        .map(|arg| Literal::from(arg.as_str()).into())
+        .collect();
+    env.borrow_mut()
+        .define_global("args", runtime.heap.list(args).into());
+
+    let exit_code = 'run: {
+        for stmt in stmts {
+            let result = stmt.run(env.clone(), ok_buf);
+            // The log is written between statements, as the heap does not
+            // hold the output.
+            runtime.heap.write_log(err_buf);
+            match result {
+                // `exit(code)` was called, which is not an error to report.
+                Err(LoxError {
+                    kind: RuntimeError::Exit(code),
+                    ..
+                }) => break 'run code,
+                Err(err) => {
+                    writeln!(err_buf, "{err}").unwrap();
+                    break 'run err.kind.exit_status();
+                }
+                Ok(_) => {}
+            }
+        }
+        0
+    };
+
+    // Frees the cycles left, e.g. of every recursive function.
+    drop(env);
+    runtime.heap.collect();
+    runtime.heap.write_log(err_buf);
+    exit_code
 }
@@ -0,0 +1,389 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    expr::ExprAst,
+    format::Comment,
+    mac::tt,
+    statement::{FunctionDef, StmtAst},
+};
+
+/// Kinds of warnings reported by the linter.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub(crate) enum Lint {
+    #[error("Unused variable '{0}'.")]
+    UnusedVariable(String),
+    #[error("Unused parameter '{0}'.")]
+    UnusedParameter(String),
+    #[error("Variable '{0}' shadows the one declared at line {1}.")]
+    Shadowing(String, usize),
+    #[error("Unreachable code after return.")]
+    UnreachableCode,
+    #[error("Condition is always {0}.")]
+    ConstantCondition(bool),
+    #[error("Function '{0}' takes {1} arguments, but {2} are given.")]
+    WrongArgumentCount(String, usize, usize),
+    #[error("Variable '{0}' is assigned to itself.")]
+    SelfAssignment(String),
+}
+
+impl Lint {
+    /// The code used in the output and in `// lint: allow(...)` directives.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable(_) => "unused-variable",
+            Self::UnusedParameter(_) => "unused-parameter",
+            Self::Shadowing(..) => "shadowing",
+            Self::UnreachableCode => "unreachable-code",
+            Self::ConstantCondition(_) => "constant-condition",
+            Self::WrongArgumentCount(..) => "wrong-argument-count",
+            Self::SelfAssignment(_) => "self-assignment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Warning {
+    pub(crate) line: usize,
+    pub(crate) lint: Lint,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[line {}] Warning: {} [{}]",
+            self.line,
+            self.lint,
+            self.lint.code()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    line: usize,
+    kind: BindingKind,
+    used: bool,
+    /// Number of parameters, if the name is bound to a known function.
+    arity: Option<usize>,
+}
+
+/// Walks the statements with the same scoping as the interpreter, and
+/// collects warnings.
+///
+/// Names starting with '_' are never reported as unused, and globals are
+/// not either, since they are visible to the whole program.
+pub(crate) struct Linter {
+    /// The first scope is the global one.
+    scopes: Vec<Vec<Binding>>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    pub(crate) fn new() -> Self {
+        Self {
+            scopes: vec![Vec::new()],
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Returns the warnings sorted by line, except those suppressed by a
+    /// `// lint: allow(code, ...)` comment on the same line or the line above.
+    pub(crate) fn lint(mut self, stmts: &[StmtAst], comments: &[Comment]) -> Vec<Warning> {
+        self.declare_global_functions(stmts);
+        self.stmts(stmts);
+
+        let allowed = allowed_codes(comments);
+        let mut warnings: Vec<_> = self
+            .warnings
+            .into_iter()
+            .filter(|warning| {
+                allowed
+                    .get(&warning.line)
+                    .map_or(true, |codes| !codes.contains(&warning.lint.code()))
+            })
+            .collect();
+        warnings.sort_by_key(|warning| warning.line);
+        warnings
+    }
+
+    /// Functions can call global functions defined below them, so those are
+    /// known before walking, if defined only once.
+    fn declare_global_functions(&mut self, stmts: &[StmtAst]) {
+        let mut declared = HashMap::new();
+        for stmt in stmts {
+            match stmt {
+                StmtAst::FunctionDef(def) => *declared.entry(def.name.src).or_insert(0) += 1,
+                StmtAst::VarDecl(var_decl) => {
+                    if let Some(name) = variable_name(&var_decl.var) {
+                        *declared.entry(name).or_insert(0) += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for stmt in stmts {
+            if let StmtAst::FunctionDef(def) = stmt {
+                if declared[def.name.src] == 1 {
+                    self.declare(
+                        def.name.src,
+                        def.name.line,
+                        BindingKind::Variable,
+                        Some(def.arguments.len()),
+                    );
+                }
+            }
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[StmtAst]) {
+        let mut returned = false;
+        for stmt in stmts {
+            if returned {
+                self.warn(stmt.start_line(), Lint::UnreachableCode);
+                returned = false;
+            }
+            if let StmtAst::Return(_) = stmt {
+                returned = true;
+            }
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &StmtAst) {
+        match stmt {
+            StmtAst::Expression(v) => self.expr(&v.expr),
+            StmtAst::Print(v) => self.expr(&v.expr),
+            StmtAst::VarDecl(v) => {
+                // The value is evaluated before the name is declared, so
+                // `var x = x;` reads the outer one.
+                if let Some(value) = v.value.as_ref() {
+                    self.expr(value);
+                }
+                if let Some(name) = variable_name(&v.var) {
+                    self.declare(name, v.start_line, BindingKind::Variable, None);
+                }
+            }
+            StmtAst::Block(v) => {
+                self.scopes.push(Vec::new());
+                self.stmts(&v.inner);
+                self.end_scope();
+            }
+            StmtAst::If(v) => {
+                self.condition(&v.condition, v.start_line);
+                self.stmt(&v.body);
+                if let Some(else_body) = v.else_body.as_ref() {
+                    self.stmt(else_body);
+                }
+            }
+            StmtAst::While(v) => {
+                self.condition(&v.condition, v.start_line);
+                self.stmt(&v.body);
+            }
+            StmtAst::For(v) => {
+                // The initializer is declared in the enclosing scope.
+                if let Some(initializer) = v.initializer.as_ref() {
+                    self.stmt(initializer);
+                }
+                if let Some(condition) = v.condition.as_ref() {
+                    self.expr(condition);
+                }
+                if let Some(increment) = v.increment.as_ref() {
+                    self.expr(increment);
+                }
+                self.stmt(&v.body);
+            }
+            StmtAst::FunctionDef(v) => self.function_def(v),
+            StmtAst::Return(v) => {
+                if let Some(expr) = v.expr.as_ref() {
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn function_def(&mut self, def: &FunctionDef) {
+        // Declared before the body, so that it can call itself.
+        let arity = Some(def.arguments.len());
+        self.declare(def.name.src, def.name.line, BindingKind::Variable, arity);
+
+        // Parameters and the body share a scope.
+        self.scopes.push(Vec::new());
+        for argument in &def.arguments {
+            self.declare(argument.src, argument.line, BindingKind::Parameter, None);
+        }
+        self.stmts(&def.body);
+        self.end_scope();
+    }
+
+    fn condition(&mut self, condition: &ExprAst, line: usize) {
+        let mut inner = condition;
+        while let ExprAst::Grouping(grouping) = inner {
+            inner = grouping.inner;
+        }
+
+        if let ExprAst::LiteralExpr(literal) = inner {
+            let truthy = !matches!(literal.token.token_type, tt!("false") | tt!("nil"));
+            self.warn(line, Lint::ConstantCondition(truthy));
+        }
+        self.expr(condition);
+    }
+
+    fn expr(&mut self, expr: &ExprAst) {
+        match expr {
+            ExprAst::Assign(v) => {
+                if let (ExprAst::Variable(assignee), ExprAst::Variable(value)) =
+                    (v.assignee, v.value)
+                {
+                    if assignee.var.src == value.var.src {
+                        let name = assignee.var.src.to_string();
+                        self.warn(assignee.var.line, Lint::SelfAssignment(name));
+                    }
+                }
+
+                self.expr(v.value);
+                match v.assignee {
+                    // Assigning is not a use, and the function it was bound
+                    // to may be gone.
+                    ExprAst::Variable(assignee) => {
+                        if let Some(binding) = self.resolve(assignee.var.src) {
+                            binding.arity = None;
+                        }
+                    }
+                    assignee => self.expr(assignee),
+                }
+            }
+            ExprAst::Binary(v) => {
+                self.expr(v.left);
+                self.expr(v.right);
+            }
+            ExprAst::FieldCall(v) => self.expr(v.object),
+            ExprAst::FunctionCall(v) => {
+                if let ExprAst::Variable(callee) = v.callee {
+                    let arity = self.resolve(callee.var.src).and_then(|b| b.arity);
+                    if let Some(arity) = arity.filter(|&arity| arity != v.arguments.len()) {
+                        let lint = Lint::WrongArgumentCount(
+                            callee.var.src.to_string(),
+                            arity,
+                            v.arguments.len(),
+                        );
+                        self.warn(callee.var.line, lint);
+                    }
+                }
+
+                self.expr(v.callee);
+                for argument in &v.arguments {
+                    self.expr(argument);
+                }
+            }
+            ExprAst::Grouping(v) => self.expr(v.inner),
+            ExprAst::LiteralExpr(_) => {}
+            ExprAst::Unary(v) => self.expr(v.right),
+            ExprAst::Variable(v) => {
+                if let Some(binding) = self.resolve(v.var.src) {
+                    binding.used = true;
+                }
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, line: usize, kind: BindingKind, arity: Option<usize>) {
+        let depth = self.scopes.len() - 1;
+        let redeclared = self.scopes[depth].iter().position(|b| b.name == name);
+
+        if let Some(index) = redeclared {
+            let binding = self.scopes[depth].remove(index);
+            self.report_unused(binding, depth);
+        } else if depth > 0 {
+            let outer = self.scopes[..depth]
+                .iter()
+                .rev()
+                .find_map(|scope| scope.iter().find(|b| b.name == name));
+            if let Some(outer) = outer {
+                let lint = Lint::Shadowing(name.to_string(), outer.line);
+                self.warn(line, lint);
+            }
+        }
+
+        self.scopes[depth].push(Binding {
+            name: name.to_string(),
+            line,
+            kind,
+            used: false,
+            arity,
+        });
+    }
+
+    fn resolve(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().find(|b| b.name == name))
+    }
+
+    fn end_scope(&mut self) {
+        let depth = self.scopes.len() - 1;
+        let scope = self.scopes.pop().unwrap();
+        for binding in scope {
+            self.report_unused(binding, depth);
+        }
+    }
+
+    fn report_unused(&mut self, binding: Binding, depth: usize) {
+        if binding.used || depth == 0 || binding.name.starts_with('_') {
+            return;
+        }
+
+        let lint = match binding.kind {
+            BindingKind::Variable => Lint::UnusedVariable(binding.name),
+            BindingKind::Parameter => Lint::UnusedParameter(binding.name),
+        };
+        self.warn(binding.line, lint);
+    }
+
+    fn warn(&mut self, line: usize, lint: Lint) {
+        self.warnings.push(Warning { line, lint });
+    }
+}
+
+/// The declared name, unless the target is invalid, e.g. `var a.b = 1;`,
+/// which is left to the interpreter to report.
+fn variable_name<'a>(var: &'a ExprAst) -> Option<&'a str> {
+    match var {
+        ExprAst::Variable(variable) => Some(variable.var.src),
+        _ => None,
+    }
+}
+
+/// Lines and the codes allowed on them, by `// lint: allow(code, ...)`.
+fn allowed_codes<'src>(comments: &[Comment<'src>]) -> HashMap<usize, Vec<&'src str>> {
+    let mut allowed = HashMap::new();
+    for comment in comments {
+        let codes = comment
+            .src
+            .trim_start_matches('/')
+            .trim()
+            .strip_prefix("lint:")
+            .and_then(|directive| directive.trim().strip_prefix("allow("))
+            .and_then(|directive| directive.trim_end().strip_suffix(')'));
+
+        if let Some(codes) = codes {
+            let codes: Vec<_> = codes.split(',').map(str::trim).collect();
+            for line in [comment.line, comment.line + 1] {
+                allowed
+                    .entry(line)
+                    .or_insert_with(Vec::new)
+                    .extend(codes.iter().copied());
+            }
+        }
+    }
+    allowed
+}
@@ -1,9 +1,9 @@
-use std::{cmp, fmt, ops};
+use std::{cell::RefCell, cmp, fmt, ops, rc::Rc};
 
 use crate::{
     // function::LoxFunction,
     function::{LoxFunction, RustFunction},
-    mac::impl_from,
+    mac::{impl_from, rc_rc},
 };
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -12,7 +12,8 @@ pub enum Literal {
     #[default]
     Nil,
     Number(Number),
-    String(String),
+    /// Shared, so that passing a string around does not copy it.
+    String(Rc<str>),
 }
 
 impl Literal {
@@ -42,7 +43,7 @@ impl Literal {
         }
     }
 
-    pub fn string_or<E>(self, error: E) -> Result<String, E> {
+    pub fn string_or<E>(self, error: E) -> Result<Rc<str>, E> {
         match self {
             Self::String(v) => Ok(v),
             _ => Err(error),
@@ -50,7 +51,25 @@ impl Literal {
     }
 }
 
-impl_from!(Literal: Number, String);
+impl_from!(Literal: Number);
+
+impl From<String> for Literal {
+    fn from(s: String) -> Self {
+        Literal::String(s.into())
+    }
+}
+
+impl From<Rc<str>> for Literal {
+    fn from(s: Rc<str>) -> Self {
+        Literal::String(s)
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(s: &str) -> Self {
+        Literal::String(s.into())
+    }
+}
 
 impl From<bool> for Literal {
     fn from(b: bool) -> Self {
@@ -152,6 +171,8 @@ pub(crate) enum LoxValue<'src> {
     Literal(Literal),
     RustFunction(RustFunction<'src>),
     LoxFunction(LoxFunction<'src>),
+    List(List<'src>),
+    Map(Map<'src>),
 }
 
 impl From<Literal> for LoxValue<'_> {
@@ -160,14 +181,15 @@ impl From<Literal> for LoxValue<'_> {
     }
 }
 
-impl_from!('a LoxValue: LoxFunction, RustFunction);
+impl_from!('a LoxValue: LoxFunction, RustFunction, List, Map);
 
 impl LoxValue<'_> {
-    pub fn is_literal_and<F: Fn(&Literal) -> bool>(&self, f: F) -> bool {
-        if let Self::Literal(l) = self {
-            f(l)
-        } else {
-            false
+    /// Only nil and false are falsy. Functions, lists and maps are always
+    /// truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Literal(l) => l.is_truthy(),
+            _ => true,
         }
     }
 
@@ -177,6 +199,15 @@ impl LoxValue<'_> {
             _ => Err(error),
         }
     }
+
+    /// As shown when inspecting the program, where a string is quoted not to
+    /// be confused with other values.
+    pub fn inspect(&self) -> String {
+        match self {
+            Self::Literal(Literal::String(s)) => format!("{s:?}"),
+            value => value.to_string(),
+        }
+    }
 }
 
 impl Default for LoxValue<'_> {
@@ -195,6 +226,89 @@ impl fmt::Display for LoxValue<'_> {
             // Self::LoxFunction(lf) => write!(f, "{lf}"),
             Self::RustFunction(rf) => write!(f, "{rf}"),
             Self::LoxFunction(lf) => write!(f, "{lf}"),
+            Self::List(list) => write!(f, "{list}"),
+            Self::Map(map) => write!(f, "{map}"),
+        }
+    }
+}
+
+/// Mutable list of values, shared by every variable holding it.
+#[derive(Debug, Clone)]
+pub(crate) struct List<'src>(pub(crate) Rc<RefCell<Vec<LoxValue<'src>>>>);
+
+impl<'src> List<'src> {
+    pub fn new(items: Vec<LoxValue<'src>>) -> Self {
+        Self(rc_rc!(items))
+    }
+}
+
+/// Like every Lox object, lists are equal only to themselves.
+impl PartialEq for List<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for List<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, item) in self.0.borrow().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Mutable map from strings to values, shared by every variable holding it.
+/// Keys keep their insertion order.
+#[derive(Debug, Clone)]
+pub(crate) struct Map<'src>(pub(crate) Rc<RefCell<Vec<Entry<'src>>>>);
+
+pub(crate) type Entry<'src> = (Rc<str>, LoxValue<'src>);
+
+impl<'src> Map<'src> {
+    pub fn new(entries: Vec<Entry<'src>>) -> Self {
+        Self(rc_rc!(entries))
+    }
+
+    pub fn get(&self, key: &str) -> Option<LoxValue<'src>> {
+        self.0
+            .borrow()
+            .iter()
+            .find(|(k, _)| **k == *key)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// Overwrites the value if the key already exists.
+    pub fn set(&self, key: Rc<str>, value: LoxValue<'src>) {
+        let mut entries = self.0.borrow_mut();
+        if let Some((_, existing)) = entries.iter_mut().find(|(k, _)| *k == key) {
+            *existing = value;
+        } else {
+            entries.push((key, value));
+        }
+    }
+}
+
+/// Like every Lox object, maps are equal only to themselves.
+impl PartialEq for Map<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Display for Map<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.0.borrow().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key}: {value}")?;
         }
+        write!(f, "}}")
     }
 }
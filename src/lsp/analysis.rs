@@ -0,0 +1,286 @@
+use crate::{
+    env::Runnable,
+    expr::ExprAst,
+    statement::{FunctionDef, StmtAst},
+    token::Token,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum SymbolKind<'src> {
+    /// With the names of the parameters.
+    Function(Vec<&'src str>),
+    Variable,
+    Parameter,
+}
+
+/// A name declared in the program.
+#[derive(Debug)]
+pub(super) struct Symbol<'src> {
+    pub(super) token: Token<'src>,
+    pub(super) kind: SymbolKind<'src>,
+    scope: usize,
+}
+
+impl Symbol<'_> {
+    /// e.g. `fun add(a, b)`, `var x`.
+    pub(super) fn signature(&self) -> String {
+        match &self.kind {
+            SymbolKind::Function(arguments) => {
+                format!("fun {}({})", self.token.src, arguments.join(", "))
+            }
+            SymbolKind::Variable => format!("var {}", self.token.src),
+            SymbolKind::Parameter => format!("parameter {}", self.token.src),
+        }
+    }
+}
+
+/// Lines of a block or a function body, in which its names are visible.
+#[derive(Debug)]
+struct Scope {
+    start_line: usize,
+    end_line: usize,
+}
+
+/// A function and the functions defined in its body, for document symbols.
+#[derive(Debug)]
+pub(super) struct Outline<'src> {
+    pub(super) name: Token<'src>,
+    pub(super) arguments: Vec<&'src str>,
+    pub(super) start_line: usize,
+    pub(super) end_line: usize,
+    pub(super) children: Vec<Outline<'src>>,
+}
+
+/// Names of a parsed program, resolved with the same scoping as the
+/// interpreter: a block or a function body opens a scope, and a name which
+/// is not found in any enclosing one refers to a global, wherever it is
+/// declared.
+#[derive(Debug)]
+pub(super) struct Analysis<'src> {
+    pub(super) symbols: Vec<Symbol<'src>>,
+    /// Each use of a name, and the index of its symbol if found.
+    references: Vec<(Token<'src>, Option<usize>)>,
+    /// The first scope is the global one.
+    scopes: Vec<Scope>,
+    /// Scopes being walked, with the names declared so far.
+    stack: Vec<(usize, Vec<usize>)>,
+    pub(super) outline: Vec<Outline<'src>>,
+}
+
+impl<'src> Analysis<'src> {
+    pub(super) fn new(stmts: &[StmtAst<'src>]) -> Self {
+        let mut analysis = Self {
+            symbols: Vec::new(),
+            references: Vec::new(),
+            scopes: vec![Scope {
+                start_line: 0,
+                end_line: usize::MAX,
+            }],
+            stack: vec![(0, Vec::new())],
+            outline: outline(stmts),
+        };
+        analysis.stmts(stmts);
+
+        // Globals can be used before they are declared, e.g. in a function.
+        for i in 0..analysis.references.len() {
+            if let (token, None) = analysis.references[i] {
+                analysis.references[i].1 = analysis
+                    .symbols
+                    .iter()
+                    .position(|symbol| symbol.scope == 0 && symbol.token.src == token.src);
+            }
+        }
+        analysis
+    }
+
+    /// The symbol declared or used at the position.
+    pub(super) fn symbol_at(&self, line: usize, column: usize) -> Option<&Symbol<'src>> {
+        if let Some(symbol) = self.symbols.iter().find(|s| covers(&s.token, line, column)) {
+            return Some(symbol);
+        }
+        self.references
+            .iter()
+            .find(|(token, _)| covers(token, line, column))
+            .and_then(|(_, index)| index.map(|index| &self.symbols[index]))
+    }
+
+    /// Names which can be used at the position, the innermost first.
+    pub(super) fn visible_at(&self, line: usize, column: usize) -> Vec<&Symbol<'src>> {
+        let mut visible: Vec<&Symbol> = Vec::new();
+        for symbol in self.symbols.iter().rev() {
+            let scope = &self.scopes[symbol.scope];
+            let in_scope = symbol.scope == 0
+                || (scope.start_line..=scope.end_line).contains(&line)
+                    && (symbol.token.line, symbol.token.column) < (line, column);
+
+            if in_scope && visible.iter().all(|v| v.token.src != symbol.token.src) {
+                visible.push(symbol);
+            }
+        }
+        visible.sort_by_key(|symbol| symbol.scope == 0);
+        visible
+    }
+
+    fn stmts(&mut self, stmts: &[StmtAst<'src>]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &StmtAst<'src>) {
+        match stmt {
+            StmtAst::Expression(v) => self.expr(&v.expr),
+            StmtAst::Print(v) => self.expr(&v.expr),
+            StmtAst::VarDecl(v) => {
+                if let Some(value) = v.value.as_ref() {
+                    self.expr(value);
+                }
+                if let ExprAst::Variable(variable) = &v.var {
+                    self.declare(variable.var, SymbolKind::Variable);
+                }
+            }
+            StmtAst::Block(v) => {
+                self.push_scope(v.start_line, v.line());
+                self.stmts(&v.inner);
+                self.stack.pop();
+            }
+            StmtAst::If(v) => {
+                self.expr(&v.condition);
+                self.stmt(&v.body);
+                if let Some(else_body) = v.else_body.as_ref() {
+                    self.stmt(else_body);
+                }
+            }
+            StmtAst::While(v) => {
+                self.expr(&v.condition);
+                self.stmt(&v.body);
+            }
+            StmtAst::For(v) => {
+                if let Some(initializer) = v.initializer.as_ref() {
+                    self.stmt(initializer);
+                }
+                if let Some(condition) = v.condition.as_ref() {
+                    self.expr(condition);
+                }
+                if let Some(increment) = v.increment.as_ref() {
+                    self.expr(increment);
+                }
+                self.stmt(&v.body);
+            }
+            StmtAst::FunctionDef(v) => self.function_def(v),
+            StmtAst::Return(v) => {
+                if let Some(expr) = v.expr.as_ref() {
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn function_def(&mut self, def: &FunctionDef<'src>) {
+        let arguments = def.arguments.iter().map(|token| token.src).collect();
+        self.declare(def.name, SymbolKind::Function(arguments));
+
+        self.push_scope(def.start_line, def.line);
+        for argument in &def.arguments {
+            self.declare(*argument, SymbolKind::Parameter);
+        }
+        self.stmts(&def.body);
+        self.stack.pop();
+    }
+
+    fn expr(&mut self, expr: &ExprAst<'src>) {
+        match expr {
+            ExprAst::Assign(v) => {
+                self.expr(v.assignee);
+                self.expr(v.value);
+            }
+            ExprAst::Binary(v) => {
+                self.expr(v.left);
+                self.expr(v.right);
+            }
+            ExprAst::FieldCall(v) => self.expr(v.object),
+            ExprAst::FunctionCall(v) => {
+                self.expr(v.callee);
+                for argument in &v.arguments {
+                    self.expr(argument);
+                }
+            }
+            ExprAst::Grouping(v) => self.expr(v.inner),
+            ExprAst::LiteralExpr(_) => {}
+            ExprAst::Unary(v) => self.expr(v.right),
+            ExprAst::Variable(v) => {
+                let resolved = self.stack.iter().rev().find_map(|(_, names)| {
+                    names
+                        .iter()
+                        .rev()
+                        .find(|&&index| self.symbols[index].token.src == v.var.src)
+                        .copied()
+                });
+                self.references.push((v.var, resolved));
+            }
+        }
+    }
+
+    fn declare(&mut self, token: Token<'src>, kind: SymbolKind<'src>) {
+        let (scope, names) = self.stack.last_mut().unwrap();
+        names.push(self.symbols.len());
+        self.symbols.push(Symbol {
+            token,
+            kind,
+            scope: *scope,
+        });
+    }
+
+    fn push_scope(&mut self, start_line: usize, end_line: usize) {
+        self.stack.push((self.scopes.len(), Vec::new()));
+        self.scopes.push(Scope {
+            start_line,
+            end_line,
+        });
+    }
+}
+
+/// Functions defined in the statements, nested as in the source.
+fn outline<'src>(stmts: &[StmtAst<'src>]) -> Vec<Outline<'src>> {
+    let mut functions = Vec::new();
+    for stmt in stmts {
+        outline_stmt(stmt, &mut functions);
+    }
+    functions
+}
+
+/// Adds the functions defined by the statement, or within its blocks and
+/// bodies, to `functions`.
+fn outline_stmt<'src>(stmt: &StmtAst<'src>, functions: &mut Vec<Outline<'src>>) {
+    match stmt {
+        StmtAst::FunctionDef(def) => functions.push(Outline {
+            name: def.name,
+            arguments: def.arguments.iter().map(|token| token.src).collect(),
+            start_line: def.start_line,
+            end_line: def.line(),
+            children: outline(&def.body),
+        }),
+        StmtAst::Block(block) => functions.extend(outline(&block.inner)),
+        StmtAst::If(v) => {
+            outline_stmt(&v.body, functions);
+            if let Some(else_body) = v.else_body.as_deref() {
+                outline_stmt(else_body, functions);
+            }
+        }
+        StmtAst::While(v) => outline_stmt(&v.body, functions),
+        StmtAst::For(v) => {
+            if let Some(initializer) = v.initializer.as_deref() {
+                outline_stmt(initializer, functions);
+            }
+            outline_stmt(&v.body, functions);
+        }
+        _ => {}
+    }
+}
+
+/// Whether the position is on the token, or right after it, where the
+/// cursor is after typing it.
+fn covers(token: &Token, line: usize, column: usize) -> bool {
+    let end = token.column + token.src.chars().count();
+    token.line == line && (token.column..=end).contains(&column)
+}
@@ -0,0 +1,484 @@
+mod analysis;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use self::analysis::{Analysis, Outline, SymbolKind};
+use crate::{
+    error::{IntoLoxError, LoxError},
+    expr::ExprArena,
+    json::Json,
+    native::NATIVES,
+    statement::StmtParser,
+    token::{Token, TokenStream, Tokenizer},
+};
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: f64 = -32700.0;
+const INVALID_PARAMS: f64 = -32602.0;
+const METHOD_NOT_FOUND: f64 = -32601.0;
+
+/// LSP enum values.
+const SEVERITY_ERROR: usize = 1;
+const SYMBOL_FUNCTION: usize = 12;
+const COMPLETION_FUNCTION: usize = 3;
+const COMPLETION_VARIABLE: usize = 6;
+
+/// Error response of a request.
+struct ResponseError {
+    code: f64,
+    message: String,
+}
+
+impl ResponseError {
+    fn invalid_params() -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: "Invalid params".to_string(),
+        }
+    }
+}
+
+/// Language server speaking LSP with `Content-Length` framed messages.
+/// Documents are synchronized in full, and analyzed again on every request.
+pub(crate) struct Server<W: Write> {
+    out: W,
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub(crate) fn new(out: W) -> Self {
+        Self {
+            out,
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    /// Serves until the client sends 'exit' or closes the input.
+    pub(crate) fn run<R: BufRead>(mut self, mut input: R) -> ExitCode {
+        while let Ok(Some(body)) = read_message(&mut input) {
+            match Json::parse(&body) {
+                Ok(message) => {
+                    if self.handle(&message) {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    let error = ResponseError {
+                        code: PARSE_ERROR,
+                        message: err.to_string(),
+                    };
+                    self.respond(Json::Null, Err(error));
+                }
+            }
+        }
+
+        // As the protocol says, exiting without 'shutdown' is a failure.
+        if self.shutdown {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
+    }
+
+    /// Handles a request or a notification, and returns whether to exit.
+    fn handle(&mut self, message: &Json) -> bool {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+
+        let Some(id) = message.get("id") else {
+            match method {
+                "textDocument/didOpen" => self.did_open(params),
+                "textDocument/didChange" => self.did_change(params),
+                "textDocument/didClose" => self.did_close(params),
+                "exit" => return true,
+                // e.g. 'initialized', which needs nothing to be done.
+                _ => {}
+            }
+            return false;
+        };
+
+        let result = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/documentSymbol" => self.document_symbol(params),
+            "textDocument/completion" => self.completion(params),
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method '{method}'"),
+            }),
+        };
+        self.respond(id.clone(), result);
+        false
+    }
+
+    fn did_open(&mut self, params: &Json) {
+        let document = params.get("textDocument");
+        let uri = document.and_then(|d| d.get("uri")).and_then(Json::as_str);
+        let text = document.and_then(|d| d.get("text")).and_then(Json::as_str);
+        if let (Some(uri), Some(text)) = (uri, text) {
+            self.update(uri, text.to_string());
+        }
+    }
+
+    fn did_change(&mut self, params: &Json) {
+        let uri = document_uri(params);
+        // Full synchronization, so the last change is the whole text.
+        let text = params
+            .get("contentChanges")
+            .and_then(Json::as_array)
+            .and_then(|changes| changes.last())
+            .and_then(|change| change.get("text"))
+            .and_then(Json::as_str);
+        if let (Some(uri), Some(text)) = (uri, text) {
+            self.update(uri, text.to_string());
+        }
+    }
+
+    fn did_close(&mut self, params: &Json) {
+        if let Some(uri) = document_uri(params) {
+            self.documents.remove(uri);
+            self.publish_diagnostics(uri, Vec::new());
+        }
+    }
+
+    fn update(&mut self, uri: &str, text: String) {
+        let diagnostics = diagnostics(&text);
+        self.documents.insert(uri.to_string(), text);
+        self.publish_diagnostics(uri, diagnostics);
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Json>) {
+        let params = Json::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]);
+        self.send(Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/publishDiagnostics".into()),
+            ("params", params),
+        ]));
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (uri, src, line, column) = self.position_params(params)?;
+        Ok(analyze(src, |analysis| {
+            analysis.symbol_at(line, column).map(|symbol| {
+                Json::object([
+                    ("uri", uri.into()),
+                    ("range", token_range(src, &symbol.token)),
+                ])
+            })
+        })
+        .unwrap_or(Json::Null))
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, src, line, column) = self.position_params(params)?;
+        Ok(analyze(src, |analysis| {
+            analysis.symbol_at(line, column).map(|symbol| {
+                let mut value = format!("```lox\n{}\n```", symbol.signature());
+                if let SymbolKind::Function(arguments) = &symbol.kind {
+                    value.push_str(&match arguments.len() {
+                        0 => "\nTakes no arguments.".to_string(),
+                        1 => "\nTakes 1 argument.".to_string(),
+                        n => format!("\nTakes {n} arguments."),
+                    });
+                }
+                Json::object([(
+                    "contents",
+                    Json::object([("kind", "markdown".into()), ("value", value.into())]),
+                )])
+            })
+        })
+        .unwrap_or(Json::Null))
+    }
+
+    fn document_symbol(&self, params: &Json) -> Result<Json, ResponseError> {
+        let uri = document_uri(params).ok_or_else(ResponseError::invalid_params)?;
+        let src = self.document(uri)?;
+
+        fn symbol(src: &str, function: &Outline) -> Json {
+            let end_line = src.lines().nth(function.end_line - 1).unwrap_or("");
+            let range = Json::object([
+                ("start", position(src, function.start_line, 1)),
+                (
+                    "end",
+                    position(src, function.end_line, end_line.chars().count() + 1),
+                ),
+            ]);
+            let children: Vec<_> = function.children.iter().map(|f| symbol(src, f)).collect();
+
+            Json::object([
+                ("name", function.name.src.into()),
+                (
+                    "detail",
+                    format!("({})", function.arguments.join(", ")).into(),
+                ),
+                ("kind", SYMBOL_FUNCTION.into()),
+                ("range", range),
+                ("selectionRange", token_range(src, &function.name)),
+                ("children", children.into()),
+            ])
+        }
+
+        Ok(analyze(src, |analysis| {
+            let symbols: Vec<_> = analysis.outline.iter().map(|f| symbol(src, f)).collect();
+            symbols.into()
+        }))
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, src, line, column) = self.position_params(params)?;
+        let mut items: Vec<Json> = analyze(src, |analysis| {
+            analysis
+                .visible_at(line, column)
+                .into_iter()
+                .map(|symbol| {
+                    let kind = match symbol.kind {
+                        SymbolKind::Function(_) => COMPLETION_FUNCTION,
+                        _ => COMPLETION_VARIABLE,
+                    };
+                    Json::object([
+                        ("label", symbol.token.src.into()),
+                        ("kind", kind.into()),
+                        ("detail", symbol.signature().into()),
+                    ])
+                })
+                .collect()
+        });
+
+        // Natives are globals too, unless a user definition hides them.
+        for native in NATIVES {
+            let label = Some(Json::from(native.name));
+            if items.iter().all(|item| item.get("label") != label.as_ref()) {
+                let detail = format!("fun {}({})", native.name, native.arguments.join(", "));
+                items.push(Json::object([
+                    ("label", native.name.into()),
+                    ("kind", COMPLETION_FUNCTION.into()),
+                    ("detail", detail.into()),
+                ]));
+            }
+        }
+        Ok(items.into())
+    }
+
+    fn document(&self, uri: &str) -> Result<&str, ResponseError> {
+        self.documents
+            .get(uri)
+            .map(String::as_str)
+            .ok_or_else(|| ResponseError {
+                code: INVALID_PARAMS,
+                message: format!("Unknown document '{uri}'"),
+            })
+    }
+
+    /// Document and position of `TextDocumentPositionParams`, where the
+    /// position is converted to the line and the column of tokens.
+    fn position_params<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a str, usize, usize), ResponseError> {
+        let uri = document_uri(params).ok_or_else(ResponseError::invalid_params)?;
+        let src = self.document(uri)?;
+        let (line, column) = params
+            .get("position")
+            .and_then(|position| from_position(src, position))
+            .ok_or_else(ResponseError::invalid_params)?;
+        Ok((uri, src, line, column))
+    }
+
+    fn respond(&mut self, id: Json, result: Result<Json, ResponseError>) {
+        let (key, value) = match result {
+            Ok(result) => ("result", result),
+            Err(error) => (
+                "error",
+                Json::object([
+                    ("code", error.code.into()),
+                    ("message", error.message.into()),
+                ]),
+            ),
+        };
+        self.send(Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            (key, value),
+        ]));
+    }
+
+    fn send(&mut self, message: Json) {
+        write_message(&mut self.out, &message);
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // Full synchronization.
+                ("textDocumentSync", 1.into()),
+                ("definitionProvider", true.into()),
+                ("hoverProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                ("completionProvider", Json::object([])),
+            ]),
+        ),
+        ("serverInfo", Json::object([("name", "lox".into())])),
+    ])
+}
+
+/// Reads the body of the next message, or `None` at the end of input.
+/// The debug adapter uses the same framing.
+pub(crate) fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(content_length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header",
+        ));
+    };
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub(crate) fn write_message<W: Write>(out: &mut W, message: &Json) {
+    let body = message.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+    out.flush().unwrap();
+}
+
+/// Errors of the tokenizer, or else the first error of the parser.
+fn diagnostics(src: &str) -> Vec<Json> {
+    // Errors of the tokenizer are collected as the parser pulls the tokens.
+    let mut errors = Vec::new();
+    let tokens = Tokenizer::new(src).filter_map(|token| {
+        token
+            .map_err(|err| errors.push((error_range(src, &err), err.kind.to_string())))
+            .ok()
+            .map(Ok)
+    });
+    let arena = ExprArena::default();
+    let mut stream = TokenStream::new(tokens);
+    let parse_error = StmtParser::new(&mut stream, &arena).parse_all().err();
+    // The rest is tokenized as well, for its errors. The stream itself sees
+    // none of them.
+    let _ = stream.finish();
+
+    if let Some(err) = parse_error.filter(|_| errors.is_empty()) {
+        errors.push((error_range(src, &err), err.kind.to_string()));
+    }
+
+    errors
+        .into_iter()
+        .map(|(range, message)| {
+            Json::object([
+                ("range", range),
+                ("severity", SEVERITY_ERROR.into()),
+                ("source", "lox".into()),
+                ("message", message.into()),
+            ])
+        })
+        .collect()
+}
+
+/// Runs `f` with the analysis of the source. A document being edited
+/// rarely parses as a whole, so statements which do not parse are skipped,
+/// as are tokens which do not scan.
+fn analyze<T>(src: &str, f: impl FnOnce(&Analysis) -> T) -> T {
+    let arena = ExprArena::default();
+    let tokens = Tokenizer::new(src).filter_map(|token| token.ok().map(Ok));
+    let mut stream = TokenStream::new(tokens);
+    let (stmts, _) = StmtParser::new(&mut stream, &arena).parse_recovering();
+    f(&Analysis::new(&stmts))
+}
+
+fn document_uri(params: &Json) -> Option<&str> {
+    params
+        .get("textDocument")
+        .and_then(|document| document.get("uri"))
+        .and_then(Json::as_str)
+}
+
+/// LSP position of a line and a column of tokens. LSP counts both from 0,
+/// and characters in UTF-16 code units.
+fn position(src: &str, line: usize, column: usize) -> Json {
+    let line = line.saturating_sub(1);
+    let text = src.lines().nth(line).unwrap_or("");
+    let character: usize = text.chars().take(column - 1).map(char::len_utf16).sum();
+    Json::object([("line", line.into()), ("character", character.into())])
+}
+
+/// Inverse of [`position`].
+fn from_position(src: &str, position: &Json) -> Option<(usize, usize)> {
+    let line = position.get("line")?.as_f64()? as usize;
+    let character = position.get("character")?.as_f64()? as usize;
+
+    let text = src.lines().nth(line).unwrap_or("");
+    let mut units = 0;
+    let column = text
+        .chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= character
+        })
+        .count();
+    Some((line + 1, column + 1))
+}
+
+fn token_range(src: &str, token: &Token) -> Json {
+    let end = token.column + token.src.chars().count();
+    Json::object([
+        ("start", position(src, token.line, token.column)),
+        ("end", position(src, token.line, end)),
+    ])
+}
+
+/// Of the token the error is at if known, or else of its line.
+fn error_range<E: IntoLoxError>(src: &str, err: &LoxError<E>) -> Json {
+    match (err.column, err.end_column) {
+        (Some(column), Some(end_column)) => Json::object([
+            ("start", position(src, err.line, column)),
+            ("end", position(src, err.line, end_column)),
+        ]),
+        _ => line_range(src, err.line),
+    }
+}
+
+fn line_range(src: &str, line: usize) -> Json {
+    let length = src
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or("")
+        .chars()
+        .count();
+    Json::object([
+        ("start", position(src, line, 1)),
+        ("end", position(src, line, length + 1)),
+    ])
+}
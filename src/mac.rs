@@ -145,6 +145,9 @@ macro_rules! tt {
     ("while") => {
         $crate::token::TokenType::While
     };
+    ("comment") => {
+        $crate::token::TokenType::Comment
+    };
 }
 
 pub(crate) use impl_from;
@@ -1,7 +1,17 @@
-use std::{fs, io, path::PathBuf, process::ExitCode};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-use clap::{Parser, Subcommand};
-use codecrafters_interpreter::{lox_evaluate, lox_parse, lox_run, lox_tokenize};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use codecrafters_interpreter::{
+    lox_compile, lox_dap, lox_debug, lox_evaluate, lox_fmt, lox_is_compiled, lox_lint, lox_lsp,
+    lox_parse_json_with, lox_parse_program_with, lox_parse_with, lox_run_compiled,
+    lox_run_instrumented, lox_run_with, lox_tokenize, lox_tokenize_json, Capabilities, Capability,
+    Instruments, ParseOptions, RunOptions,
+};
 
 #[non_exhaustive]
 #[derive(Debug, Parser)]
@@ -11,16 +21,217 @@ struct Cli {
     command: LoxCommand,
 }
 
+/// Every command reads the source from `file_name`, or from stdin if it is
+/// '-'.
 #[derive(Debug, Subcommand)]
 enum LoxCommand {
-    Tokenize { file_name: PathBuf },
-    Parse { file_name: PathBuf },
-    Evaluate { file_name: PathBuf },
-    Run { file_name: PathBuf },
+    Tokenize {
+        file_name: PathBuf,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Prints the expression as an S-expression, or with '--format json',
+    /// the statements of the whole program.
+    Parse {
+        file_name: PathBuf,
+        /// Parse the whole program, and print each statement as an
+        /// S-expression.
+        #[arg(long)]
+        program: bool,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+        /// Print the tree as '-O' makes it for 'run'.
+        #[arg(short = 'O')]
+        optimize: bool,
+    },
+    Evaluate {
+        file_name: PathBuf,
+    },
+    /// Prints the source formatted.
+    Fmt {
+        file_name: PathBuf,
+        /// Print nothing, but fail if the source is not formatted.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Reports suspicious code. A warning can be suppressed with a
+    /// `// lint: allow(code)` comment on its line or the line above.
+    Lint {
+        file_name: PathBuf,
+    },
+    /// Serves the Language Server Protocol over stdin and stdout.
+    Lsp,
+    /// Writes the program parsed and resolved, for 'run' to run without
+    /// parsing it again. It is rejected once the source changes.
+    Compile {
+        file_name: PathBuf,
+        /// Where to write it, by default the source's path with the '.loxc'
+        /// extension, or stdout if the source is read from stdin.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Fold constants and remove dead branches, as '-O' of 'run' does.
+        #[arg(short = 'O')]
+        optimize: bool,
+    },
+    /// Runs the script, or the program written by 'compile'.
+    Run {
+        file_name: PathBuf,
+        /// Print the calls and the time spent in each function to stderr
+        /// after the run.
+        #[arg(long)]
+        profile: bool,
+        /// Write the call stacks to the file, folded for flame graph tools.
+        /// Implies '--profile'.
+        #[arg(long, value_name = "FILE")]
+        profile_folded: Option<PathBuf>,
+        /// Write which lines and branches ran to the file, as LCOV.
+        #[arg(long, value_name = "FILE")]
+        coverage: Option<PathBuf>,
+        /// Print each statement, call and assignment to stderr as it runs.
+        #[arg(long)]
+        trace: bool,
+        /// Trace only the calls of the function of this name. Implies
+        /// '--trace'.
+        #[arg(long, value_name = "FUNCTION")]
+        trace_filter: Option<String>,
+        #[command(flatten)]
+        options: RunArgs,
+    },
+    /// Runs the script under an interactive prompt, read from stdin. The
+    /// script must be a file.
+    Debug {
+        file_name: PathBuf,
+        #[command(flatten)]
+        options: RunArgs,
+    },
+    /// Serves the Debug Adapter Protocol over stdin and stdout. The script
+    /// is given by the 'launch' request.
+    Dap,
 }
 
-fn read(file_name: PathBuf) -> String {
-    fs::read_to_string(file_name).unwrap_or_default()
+/// Output of 'tokenize' and 'parse'. With 'json', errors are printed to
+/// stdout as part of the output as well.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Args)]
+struct RunArgs {
+    /// Capabilities granted to the script, on top of 'pure'.
+    /// One of: pure, time, random, fs_read, fs_write, env, process.
+    #[arg(long, value_delimiter = ',')]
+    allow: Option<Vec<Capability>>,
+    /// Seed for `random`, `random_int` and `shuffle`, to make the run
+    /// reproducible.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Print a line to stderr for each collection of reference cycles.
+    #[arg(long)]
+    gc_log: bool,
+    /// Fold constants and remove dead branches before running.
+    #[arg(short = 'O')]
+    optimize: bool,
+    /// Arguments passed to the script as the `args` list.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+impl From<RunArgs> for RunOptions {
+    fn from(
+        RunArgs {
+            allow,
+            seed,
+            gc_log,
+            optimize,
+            args,
+        }: RunArgs,
+    ) -> Self {
+        let capabilities = match allow {
+            Some(allow) => allow.into_iter().fold(
+                Capabilities::none().with(Capability::Pure),
+                Capabilities::with,
+            ),
+            None => Capabilities::default(),
+        };
+        RunOptions {
+            capabilities,
+            args,
+            seed,
+            gc_log,
+            optimize,
+        }
+    }
+}
+
+/// Exit code for a source file which cannot be read, as `EX_NOINPUT` of
+/// sysexits.h.
+const NO_INPUT_EXIT_CODE: u8 = 66;
+
+/// Exit code for an output file which cannot be created, as `EX_CANTCREAT`
+/// of sysexits.h.
+const CANT_CREATE_EXIT_CODE: u8 = 73;
+
+/// Reads the file, or stdin if the file name is '-'.
+fn read_bytes(file_name: &Path) -> io::Result<Vec<u8>> {
+    if file_name == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(file_name)
+    }
+}
+
+/// Reads the source file, or stdin if the file name is '-'.
+fn read(file_name: &Path) -> io::Result<String> {
+    utf8(read_bytes(file_name)?)
+}
+
+fn utf8(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Read the source without allowing error. The source is read by `read`,
+/// unless another result is given.
+macro_rules! read_src {
+    ($file_name:expr, $err_buf:expr) => {
+        read_src!(read(&$file_name), $file_name, $err_buf)
+    };
+    ($read:expr, $file_name:expr, $err_buf:expr) => {
+        match $read {
+            Ok(src) => src,
+            Err(err) => {
+                writeln!(
+                    $err_buf,
+                    "Error: Could not read '{}': {err}",
+                    $file_name.display()
+                )
+                .unwrap();
+                return ExitCode::from(NO_INPUT_EXIT_CODE);
+            }
+        }
+    };
+}
+
+/// Create the output file without allowing error.
+macro_rules! create_file {
+    ($file_name:expr, $err_buf:expr) => {
+        match fs::File::create(&$file_name) {
+            Ok(file) => file,
+            Err(err) => {
+                writeln!(
+                    $err_buf,
+                    "Error: Could not create '{}': {err}",
+                    $file_name.display()
+                )
+                .unwrap();
+                return ExitCode::from(CANT_CREATE_EXIT_CODE);
+            }
+        }
+    };
 }
 
 fn main() -> ExitCode {
@@ -28,21 +239,139 @@ fn main() -> ExitCode {
     let mut stdout = io::stdout();
     let mut stderr = io::stderr();
     match arg.command {
-        LoxCommand::Tokenize { file_name } => {
-            let src = read(file_name);
-            lox_tokenize(&src, &mut stdout, &mut stderr)
+        LoxCommand::Tokenize { file_name, format } => {
+            let src = read_src!(file_name, stderr);
+            match format {
+                Format::Text => lox_tokenize(&src, &mut stdout, &mut stderr),
+                Format::Json => lox_tokenize_json(&src, &mut stdout),
+            }
         }
-        LoxCommand::Parse { file_name } => {
-            let src = read(file_name);
-            lox_parse(&src, &mut stdout, &mut stderr)
+        LoxCommand::Parse {
+            file_name,
+            program,
+            format,
+            optimize,
+        } => {
+            let src = read_src!(file_name, stderr);
+            let options = ParseOptions { optimize };
+            match format {
+                Format::Text if program => {
+                    lox_parse_program_with(&src, &options, &mut stdout, &mut stderr)
+                }
+                Format::Text => lox_parse_with(&src, &options, &mut stdout, &mut stderr),
+                Format::Json => lox_parse_json_with(&src, &options, &mut stdout),
+            }
         }
         LoxCommand::Evaluate { file_name } => {
-            let src = read(file_name);
+            let src = read_src!(file_name, stderr);
             lox_evaluate(&src, &mut stdout, &mut stderr)
         }
-        LoxCommand::Run { file_name } => {
-            let src = read(file_name);
-            lox_run(&src, &mut stdout, &mut stderr)
+        LoxCommand::Fmt { file_name, check } => {
+            let src = read_src!(file_name, stderr);
+            lox_fmt(&src, check, &mut stdout, &mut stderr)
+        }
+        LoxCommand::Lint { file_name } => {
+            let src = read_src!(file_name, stderr);
+            lox_lint(&src, &mut stdout, &mut stderr)
+        }
+        LoxCommand::Lsp => lox_lsp(io::stdin().lock(), stdout),
+        LoxCommand::Compile {
+            file_name,
+            output,
+            optimize,
+        } => {
+            let src = read_src!(file_name, stderr);
+            let from_stdin = file_name == Path::new("-");
+            let path = match fs::canonicalize(&file_name) {
+                Ok(path) if !from_stdin => path.display().to_string(),
+                _ => String::new(),
+            };
+            let mut compiled = Vec::new();
+            let exit_code = lox_compile(&src, &path, optimize, &mut compiled, &mut stderr);
+            if exit_code != ExitCode::SUCCESS {
+                return exit_code;
+            }
+
+            match output {
+                None if from_stdin => stdout.write_all(&compiled).unwrap(),
+                output => {
+                    let output = output.unwrap_or_else(|| file_name.with_extension("loxc"));
+                    create_file!(output, stderr).write_all(&compiled).unwrap();
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        LoxCommand::Run {
+            file_name,
+            profile,
+            profile_folded,
+            coverage,
+            trace,
+            trace_filter,
+            options,
+        } => {
+            let file = read_src!(read_bytes(&file_name), file_name, stderr);
+            let options = options.into();
+            if lox_is_compiled(&file) {
+                if coverage.is_some()
+                    || profile
+                    || profile_folded.is_some()
+                    || trace
+                    || trace_filter.is_some()
+                {
+                    writeln!(
+                        stderr,
+                        "Error: '--profile', '--coverage' and '--trace' need the source."
+                    )
+                    .unwrap();
+                    return ExitCode::from(2);
+                }
+                return lox_run_compiled(&file, &options, &mut stdout, &mut stderr);
+            }
+
+            let src = read_src!(utf8(file), file_name, stderr);
+            if coverage.is_none()
+                && !profile
+                && profile_folded.is_none()
+                && !trace
+                && trace_filter.is_none()
+            {
+                return lox_run_with(&src, &options, &mut stdout, &mut stderr);
+            }
+
+            // The files are created before running, not to run in vain.
+            let mut lcov = match &coverage {
+                Some(path) => Some(create_file!(path, stderr)),
+                None => None,
+            };
+            let mut folded = match &profile_folded {
+                Some(path) => Some(create_file!(path, stderr)),
+                None => None,
+            };
+            let (mut report, mut trace_out) = (io::stderr(), io::stderr());
+            let path = file_name.display().to_string();
+            let instruments = Instruments {
+                profile: (profile || folded.is_some()).then_some(&mut report as &mut dyn Write),
+                profile_folded: folded.as_mut().map(|file| file as &mut dyn Write),
+                coverage: lcov
+                    .as_mut()
+                    .map(|file| (path.as_str(), file as &mut dyn Write)),
+                trace: (trace || trace_filter.is_some())
+                    .then_some(&mut trace_out as &mut dyn Write),
+                trace_filter: trace_filter.as_deref(),
+            };
+            lox_run_instrumented(&src, &options, instruments, &mut stdout, &mut stderr)
+        }
+        LoxCommand::Debug { file_name, options } => {
+            // The prompt is read from stdin, so the script can't be.
+            if file_name == Path::new("-") {
+                writeln!(stderr, "Error: 'debug' needs a script file, not stdin.").unwrap();
+                return ExitCode::from(2);
+            }
+            let src = read_src!(file_name, stderr);
+            let input = io::stdin().lock();
+            lox_debug(&src, &options.into(), input, &mut stdout, &mut stderr)
         }
+        LoxCommand::Dap => lox_dap(io::stdin().lock(), stdout),
     }
 }
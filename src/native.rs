@@ -0,0 +1,417 @@
+use std::{
+    env,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    capability::Capability,
+    env::RuntimeError::{self, *},
+    function::RustFunction,
+    json::Json,
+    literal::{List, Literal, LoxValue, Map, Number},
+    runtime::Runtime,
+};
+
+/// Signature of a builtin function, and the capability a script needs to see
+/// it.
+pub(crate) struct Native {
+    pub(crate) name: &'static str,
+    pub(crate) arguments: &'static [&'static str],
+    /// How many of the trailing arguments can be omitted. They are nil then.
+    pub(crate) optional: usize,
+    pub(crate) capability: Capability,
+}
+
+impl Native {
+    pub(crate) fn find(name: &str) -> Option<&'static Native> {
+        NATIVES.iter().find(|native| native.name == name)
+    }
+
+    pub(crate) fn to_rust_function<'a>(&self) -> RustFunction<'a> {
+        RustFunction {
+            name: self.name,
+            arguments: self.arguments.to_vec(),
+            optional: self.optional,
+        }
+    }
+}
+
+/// Every builtin function. [`crate::env::Env::new`] installs the ones whose
+/// capability is granted.
+pub(crate) const NATIVES: &[Native] = &[
+    Native {
+        name: "len",
+        arguments: &["value"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "list",
+        arguments: &[],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "push",
+        arguments: &["list", "value"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "map",
+        arguments: &[],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "get",
+        arguments: &["collection", "key"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "set",
+        arguments: &["map", "key", "value"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "keys",
+        arguments: &["map"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "json_parse",
+        arguments: &["json"],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "json_stringify",
+        arguments: &["value", "indent"],
+        optional: 1,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "gc_stats",
+        arguments: &[],
+        optional: 0,
+        capability: Capability::Pure,
+    },
+    Native {
+        name: "clock",
+        arguments: &[],
+        optional: 0,
+        capability: Capability::Time,
+    },
+    Native {
+        name: "random",
+        arguments: &[],
+        optional: 0,
+        capability: Capability::Random,
+    },
+    Native {
+        name: "random_int",
+        arguments: &["lo", "hi"],
+        optional: 0,
+        capability: Capability::Random,
+    },
+    Native {
+        name: "shuffle",
+        arguments: &["list"],
+        optional: 0,
+        capability: Capability::Random,
+    },
+    Native {
+        name: "getenv",
+        arguments: &["name"],
+        optional: 0,
+        capability: Capability::Env,
+    },
+    Native {
+        name: "exit",
+        arguments: &["code"],
+        optional: 0,
+        capability: Capability::Process,
+    },
+];
+
+/// Calls the builtin function with given name. Number of arguments should be
+/// already checked.
+pub(crate) fn call<'a>(
+    name: &str,
+    arguments: Vec<LoxValue<'a>>,
+    runtime: &Runtime<'a>,
+) -> Result<LoxValue<'a>, RuntimeError> {
+    let mut arguments = arguments.into_iter();
+    let mut next = || arguments.next().unwrap_or_default();
+
+    match name {
+        "len" => len(next()),
+        "list" => Ok(runtime.heap.list(Vec::new()).into()),
+        "push" => push(next(), next()),
+        "map" => Ok(runtime.heap.map(Vec::new()).into()),
+        "get" => get(next(), next()),
+        "set" => set(next(), next(), next()),
+        "keys" => keys(next(), runtime),
+        "json_parse" => json_parse(next(), runtime),
+        "json_stringify" => json_stringify(next(), next()),
+        "gc_stats" => Ok(gc_stats(runtime)),
+        "clock" => Ok(clock()),
+        "random" => Ok(Literal::Number(Number(runtime.rng.borrow_mut().next_f64())).into()),
+        "random_int" => random_int(next(), next(), runtime),
+        "shuffle" => shuffle(next(), runtime),
+        "getenv" => getenv(next()),
+        "exit" => exit(next()),
+        rest => unreachable!("there are no builtin function named {rest}"),
+    }
+}
+
+fn len(value: LoxValue<'_>) -> Result<LoxValue<'_>, RuntimeError> {
+    let len = match value {
+        LoxValue::Literal(Literal::String(s)) => s.chars().count(),
+        LoxValue::List(list) => list.0.borrow().len(),
+        LoxValue::Map(map) => map.0.borrow().len(),
+        _ => return Err(OperandMustBe("string, list or map")),
+    };
+
+    Ok(Literal::Number(Number(len as f64)).into())
+}
+
+fn push<'a>(list: LoxValue<'a>, value: LoxValue<'a>) -> Result<LoxValue<'a>, RuntimeError> {
+    let list = expect_list(list)?;
+    list.0.borrow_mut().push(value);
+    Ok(list.into())
+}
+
+/// Item of a list at the index, or value of a map at the key (nil if absent).
+fn get<'a>(collection: LoxValue<'a>, key: LoxValue<'a>) -> Result<LoxValue<'a>, RuntimeError> {
+    let list = match collection {
+        LoxValue::List(list) => list,
+        LoxValue::Map(map) => return Ok(map.get(&expect_string(key)?).unwrap_or_default()),
+        _ => return Err(OperandMustBe("list or map")),
+    };
+    let index = expect_number(key)?;
+    let items = list.0.borrow();
+
+    if index < 0.0 || index.fract() != 0.0 || index as usize >= items.len() {
+        return Err(IndexOutOfRange(index.to_string(), items.len()));
+    }
+    Ok(items[index as usize].clone())
+}
+
+fn set<'a>(
+    map: LoxValue<'a>,
+    key: LoxValue<'a>,
+    value: LoxValue<'a>,
+) -> Result<LoxValue<'a>, RuntimeError> {
+    let map = expect_map(map)?;
+    map.set(expect_string(key)?, value);
+    Ok(map.into())
+}
+
+fn keys<'a>(map: LoxValue<'a>, runtime: &Runtime<'a>) -> Result<LoxValue<'a>, RuntimeError> {
+    let map = expect_map(map)?;
+    let keys = map
+        .0
+        .borrow()
+        .iter()
+        .map(|(key, _)| Literal::String(key.clone()).into())
+        .collect();
+    Ok(runtime.heap.list(keys).into())
+}
+
+fn json_parse<'a>(json: LoxValue<'a>, runtime: &Runtime<'a>) -> Result<LoxValue<'a>, RuntimeError> {
+    let json =
+        Json::parse(&expect_string(json)?).map_err(|err| InvalidJson(err.message, err.offset))?;
+    Ok(from_json(json, runtime))
+}
+
+/// Indent is the number of spaces per level. Without it, the JSON is on a
+/// single line.
+fn json_stringify<'a>(
+    value: LoxValue<'a>,
+    indent: LoxValue<'a>,
+) -> Result<LoxValue<'a>, RuntimeError> {
+    let indent = match indent {
+        LoxValue::Literal(Literal::Nil) => 0,
+        indent => expect_number(indent)?.max(0.0) as usize,
+    };
+    let json = to_json(&value, &mut Vec::new())?;
+    Ok(Literal::from(json.to_string_indented(indent)).into())
+}
+
+fn from_json<'a>(json: Json, runtime: &Runtime<'a>) -> LoxValue<'a> {
+    match json {
+        Json::Null => Literal::Nil.into(),
+        Json::Bool(b) => Literal::Boolean(b).into(),
+        Json::Number(n) => Literal::Number(Number(n)).into(),
+        Json::String(s) => Literal::from(s).into(),
+        Json::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| from_json(item, runtime))
+                .collect();
+            runtime.heap.list(items).into()
+        }
+        // Set one by one as `set` does, so that the last of repeated keys
+        // wins as in JSON.parse.
+        Json::Object(fields) => {
+            let map = runtime.heap.map(Vec::new());
+            for (key, value) in fields {
+                map.set(key.into(), from_json(value, runtime));
+            }
+            map.into()
+        }
+    }
+}
+
+/// `visiting` holds the collections being converted, to reject cycles.
+fn to_json(value: &LoxValue<'_>, visiting: &mut Vec<*const ()>) -> Result<Json, RuntimeError> {
+    let json = match value {
+        LoxValue::Literal(Literal::Nil) => Json::Null,
+        LoxValue::Literal(Literal::Boolean(b)) => Json::Bool(*b),
+        LoxValue::Literal(Literal::Number(Number(n))) => Json::Number(*n),
+        LoxValue::Literal(Literal::String(s)) => Json::String(s.to_string()),
+        LoxValue::List(list) => {
+            let ptr = Rc::as_ptr(&list.0) as *const ();
+            if visiting.contains(&ptr) {
+                return Err(NotJsonSerializable("cyclic list".to_string()));
+            }
+            visiting.push(ptr);
+            let items = list
+                .0
+                .borrow()
+                .iter()
+                .map(|item| to_json(item, visiting))
+                .collect::<Result<_, _>>()?;
+            visiting.pop();
+            Json::Array(items)
+        }
+        LoxValue::Map(map) => {
+            let ptr = Rc::as_ptr(&map.0) as *const ();
+            if visiting.contains(&ptr) {
+                return Err(NotJsonSerializable("cyclic map".to_string()));
+            }
+            visiting.push(ptr);
+            let fields = map
+                .0
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((key.to_string(), to_json(value, visiting)?)))
+                .collect::<Result<_, _>>()?;
+            visiting.pop();
+            Json::Object(fields)
+        }
+        function @ (LoxValue::LoxFunction(_) | LoxValue::RustFunction(_)) => {
+            return Err(NotJsonSerializable(function.to_string()))
+        }
+    };
+    Ok(json)
+}
+
+/// Counts of the collector of reference cycles, as a map.
+fn gc_stats<'a>(runtime: &Runtime<'a>) -> LoxValue<'a> {
+    let stats = runtime.heap.stats();
+    let entries = [
+        ("collections", stats.collections),
+        ("freed", stats.freed),
+        ("live", stats.live),
+    ]
+    .into_iter()
+    .map(|(key, count)| (key.into(), Literal::Number(Number(count as f64)).into()))
+    .collect();
+    runtime.heap.map(entries).into()
+}
+
+fn clock<'a>() -> LoxValue<'a> {
+    let elapsed_secs_from_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as f64;
+
+    LoxValue::Literal(Literal::Number(Number(elapsed_secs_from_epoch)))
+}
+
+/// Every integer up to this is a number exactly.
+const MAX_EXACT_INTEGER: f64 = (1u64 << 53) as f64;
+
+/// Uniform integer in [lo, hi].
+fn random_int<'a>(
+    lo: LoxValue<'a>,
+    hi: LoxValue<'a>,
+    runtime: &Runtime,
+) -> Result<LoxValue<'a>, RuntimeError> {
+    let (lo, hi) = (expect_number(lo)?, expect_number(hi)?);
+    if lo.fract() != 0.0 || hi.fract() != 0.0 || lo > hi {
+        return Err(OperandMustBe("two integers, lo <= hi"));
+    }
+
+    let too_wide = OperandMustBe("integers at most 2^53 apart");
+    if hi - lo > MAX_EXACT_INTEGER {
+        return Err(too_wide);
+    }
+    let span = ((hi - lo) as u64).checked_add(1).ok_or(too_wide)?;
+    let offset = runtime.rng.borrow_mut().below(span);
+    Ok(Literal::Number(Number(lo + offset as f64)).into())
+}
+
+/// Shuffles the list in place (Fisher-Yates), and returns it.
+fn shuffle<'a>(list: LoxValue<'a>, runtime: &Runtime) -> Result<LoxValue<'a>, RuntimeError> {
+    let list = expect_list(list)?;
+    {
+        let mut items = list.0.borrow_mut();
+        let mut rng = runtime.rng.borrow_mut();
+        for i in (1..items.len()).rev() {
+            let j = rng.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+    Ok(list.into())
+}
+
+/// Value of the environment variable, or nil if it isn't set.
+fn getenv(name: LoxValue<'_>) -> Result<LoxValue<'_>, RuntimeError> {
+    let name = expect_string(name)?;
+    let value = env::var(&*name).map_or(Literal::Nil, Literal::from);
+    Ok(value.into())
+}
+
+/// Stops the whole script. It is reported as an error, so that every
+/// statement and function call on the way unwinds.
+fn exit(code: LoxValue<'_>) -> Result<LoxValue<'_>, RuntimeError> {
+    let code = expect_number(code)?;
+    if !(0.0..=255.0).contains(&code) || code.fract() != 0.0 {
+        return Err(OperandMustBe("an integer between 0 and 255"));
+    }
+    Err(Exit(code as u8))
+}
+
+fn expect_string(value: LoxValue<'_>) -> Result<Rc<str>, RuntimeError> {
+    value
+        .literal_or(OperandMustBe("string"))?
+        .string_or(OperandMustBe("string"))
+}
+
+fn expect_number(value: LoxValue<'_>) -> Result<f64, RuntimeError> {
+    value
+        .literal_or(OperandMustBe("number"))?
+        .number_or(OperandMustBe("number"))
+        .map(|Number(n)| n)
+}
+
+fn expect_map(value: LoxValue<'_>) -> Result<Map<'_>, RuntimeError> {
+    match value {
+        LoxValue::Map(map) => Ok(map),
+        _ => Err(OperandMustBe("map")),
+    }
+}
+
+fn expect_list(value: LoxValue<'_>) -> Result<List<'_>, RuntimeError> {
+    match value {
+        LoxValue::List(list) => Ok(list),
+        _ => Err(OperandMustBe("list")),
+    }
+}
@@ -0,0 +1,234 @@
+//! Optional pass between parsing and running, given by `-O`. It folds
+//! operations on literals, and removes the branches and loops whose
+//! condition is a literal.
+//!
+//! Nothing that fails at runtime is folded, e.g. `"a" - 1`, so that it still
+//! fails at its line.
+
+use crate::{
+    env::Evaluatable,
+    expr::{Assign, Binary, BinaryOp, ExprArena, ExprAst, Grouping, LiteralExpr, Unary, UnaryOp},
+    literal::Literal,
+    statement::{Block, StmtAst},
+    token::Token,
+};
+
+/// New expressions are allocated in the arena of the program.
+pub(crate) fn optimize<'src>(
+    stmts: Vec<StmtAst<'src>>,
+    arena: &'src ExprArena<'src>,
+) -> Vec<StmtAst<'src>> {
+    Optimizer { arena }.stmts(stmts)
+}
+
+/// The optimized form of a single expression, for 'parse -O'.
+pub(crate) fn expr<'src>(expr: &ExprAst<'src>, arena: &'src ExprArena<'src>) -> ExprAst<'src> {
+    Optimizer { arena }.expr(expr)
+}
+
+struct Optimizer<'src> {
+    arena: &'src ExprArena<'src>,
+}
+
+impl<'src> Optimizer<'src> {
+    fn stmts(&self, stmts: Vec<StmtAst<'src>>) -> Vec<StmtAst<'src>> {
+        stmts
+            .into_iter()
+            .filter_map(|stmt| self.stmt(stmt))
+            .collect()
+    }
+
+    /// None if the statement does nothing.
+    fn stmt(&self, stmt: StmtAst<'src>) -> Option<StmtAst<'src>> {
+        let stmt = match stmt {
+            StmtAst::Expression(mut v) => {
+                v.expr = self.expr(&v.expr);
+                v.into()
+            }
+            StmtAst::Print(mut v) => {
+                v.expr = self.expr(&v.expr);
+                v.into()
+            }
+            StmtAst::VarDecl(mut v) => {
+                v.value = v.value.map(|value| self.expr(&value));
+                v.into()
+            }
+            StmtAst::Block(mut v) => {
+                v.inner = self.stmts(v.inner);
+                v.into()
+            }
+            StmtAst::If(mut v) => {
+                v.condition = self.expr(&v.condition);
+                if let Some(condition) = literal(&v.condition) {
+                    let taken = match condition.is_truthy() {
+                        true => Some(*v.body),
+                        false => v.else_body.map(|else_body| *else_body),
+                    };
+                    return taken.and_then(|taken| self.stmt(taken));
+                }
+                v.body = Box::new(self.body(*v.body));
+                v.else_body = v.else_body.map(|else_body| Box::new(self.body(*else_body)));
+                v.into()
+            }
+            StmtAst::While(mut v) => {
+                v.condition = self.expr(&v.condition);
+                if literal(&v.condition).is_some_and(|condition| !condition.is_truthy()) {
+                    return None;
+                }
+                v.body = Box::new(self.body(*v.body));
+                v.into()
+            }
+            StmtAst::For(mut v) => {
+                v.initializer = v
+                    .initializer
+                    .and_then(|initializer| self.stmt(*initializer).map(Box::new));
+                v.condition = v.condition.map(|condition| self.expr(&condition));
+                v.increment = v.increment.map(|increment| self.expr(&increment));
+                v.body = Box::new(self.body(*v.body));
+                v.into()
+            }
+            StmtAst::FunctionDef(mut v) => {
+                // Not shared yet, as nothing has run.
+                let def = std::rc::Rc::make_mut(&mut v);
+                def.body = self.stmts(std::mem::take(&mut def.body));
+                StmtAst::FunctionDef(v)
+            }
+            StmtAst::Return(mut v) => {
+                v.expr = v.expr.map(|expr| self.expr(&expr));
+                v.into()
+            }
+        };
+        Some(stmt)
+    }
+
+    /// The body of a branch or a loop, which cannot be removed, but emptied.
+    fn body(&self, body: StmtAst<'src>) -> StmtAst<'src> {
+        let start_line = body.start_line();
+        self.stmt(body).unwrap_or_else(|| {
+            Block {
+                inner: Vec::new(),
+                start_line,
+                line: start_line,
+            }
+            .into()
+        })
+    }
+
+    /// Unchanged subtrees are shared with the original expression.
+    fn expr(&self, expr: &ExprAst<'src>) -> ExprAst<'src> {
+        match expr {
+            ExprAst::Assign(Assign { assignee, value }) => Assign {
+                // A grouping is not a valid target, so that it is kept.
+                assignee,
+                value: self.alloc(value),
+            }
+            .into(),
+            ExprAst::Binary(Binary { left, op, right }) => {
+                self.binary(self.expr(left), *op, self.expr(right))
+            }
+            ExprAst::FieldCall(v) => {
+                let mut v = v.clone();
+                v.object = self.alloc(v.object);
+                v.into()
+            }
+            ExprAst::FunctionCall(v) => {
+                let mut v = v.clone();
+                v.callee = self.alloc(v.callee);
+                v.arguments = v
+                    .arguments
+                    .iter()
+                    .map(|argument| self.expr(argument))
+                    .collect();
+                v.into()
+            }
+            ExprAst::Grouping(Grouping { inner }) => self.expr(inner),
+            ExprAst::Unary(Unary { op, right }) => {
+                let right = self.expr(right);
+                let folded = match (op, literal(&right)) {
+                    (UnaryOp::Bang, Some(value)) => Some((!value.is_truthy()).into()),
+                    (UnaryOp::Minus, Some(Literal::Number(n))) => Some(Literal::Number(-*n)),
+                    _ => None,
+                };
+                match folded {
+                    Some(value) => folded_literal(&right, right.line(), value),
+                    None => Unary {
+                        op: *op,
+                        right: self.arena.alloc(right),
+                    }
+                    .into(),
+                }
+            }
+            rest @ (ExprAst::LiteralExpr(_) | ExprAst::Variable(_)) => rest.clone(),
+        }
+    }
+
+    /// The optimized form of an operand, in the arena.
+    fn alloc(&self, expr: &ExprAst<'src>) -> &'src ExprAst<'src> {
+        self.arena.alloc(self.expr(expr))
+    }
+
+    fn binary(&self, left: ExprAst<'src>, op: BinaryOp, right: ExprAst<'src>) -> ExprAst<'src> {
+        use Literal::{Number as N, String as S};
+
+        let original = |left: ExprAst<'src>, right: ExprAst<'src>| -> ExprAst<'src> {
+            Binary {
+                left: self.arena.alloc(left),
+                op,
+                right: self.arena.alloc(right),
+            }
+            .into()
+        };
+        let Some(l) = literal(&left) else {
+            return original(left, right);
+        };
+
+        // Only the left side has to be a literal to know which side is taken.
+        match op {
+            BinaryOp::And if !l.is_truthy() => {
+                return folded_literal(&left, left.line(), false.into())
+            }
+            BinaryOp::And => return right,
+            BinaryOp::Or if l.is_truthy() => return left,
+            BinaryOp::Or => return right,
+            _ => {}
+        }
+
+        let Some(r) = literal(&right) else {
+            return original(left, right);
+        };
+        let folded: Literal = match (op, l, r) {
+            (BinaryOp::Plus, N(l), N(r)) => N(*l + *r),
+            (BinaryOp::Plus, S(l), S(r)) => self.arena.string(&format!("{l}{r}")).into(),
+            (BinaryOp::Minus, N(l), N(r)) => N(*l - *r),
+            (BinaryOp::Star, N(l), N(r)) => N(*l * *r),
+            (BinaryOp::Slash, N(l), N(r)) => N(*l / *r),
+            (BinaryOp::Greater, N(l), N(r)) => (l > r).into(),
+            (BinaryOp::GreaterEqual, N(l), N(r)) => (l >= r).into(),
+            (BinaryOp::Less, N(l), N(r)) => (l < r).into(),
+            (BinaryOp::LessEqual, N(l), N(r)) => (l <= r).into(),
+            (BinaryOp::EqualEqual, l, r) => (l == r).into(),
+            (BinaryOp::BangEqual, l, r) => (l != r).into(),
+            // Fails when run.
+            _ => return original(left, right),
+        };
+        // A binary operation fails at the line of its right side.
+        folded_literal(&left, right.line(), folded)
+    }
+}
+
+fn literal<'a>(expr: &'a ExprAst) -> Option<&'a Literal> {
+    match expr {
+        ExprAst::LiteralExpr(v) => Some(&v.value),
+        _ => None,
+    }
+}
+
+/// A literal in place of an expression starting with `first`. It keeps the
+/// line of the expression, which an operation using it fails at.
+fn folded_literal<'src>(first: &ExprAst<'src>, line: usize, value: Literal) -> ExprAst<'src> {
+    let token = Token {
+        line,
+        ..*first.start_token()
+    };
+    LiteralExpr { token, value }.into()
+}
@@ -0,0 +1,191 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    literal::LoxValue,
+    runtime::{Call, Hook},
+};
+
+/// Statistics of a function, over all of its calls.
+#[derive(Debug)]
+struct Function {
+    name: String,
+    defined_at: Option<usize>,
+    calls: usize,
+    /// Time spent in the calls, with their callees. Calls made while another
+    /// call of the same function runs, i.e. recursion, are not counted again.
+    inclusive: Duration,
+    /// Time spent in the function itself.
+    exclusive: Duration,
+    /// Calls of the function being made.
+    active: usize,
+}
+
+impl Function {
+    /// e.g. `fib:3` for a function defined at line 3, or `clock` for a
+    /// native.
+    fn label(&self) -> String {
+        match self.defined_at {
+            Some(line) => format!("{}:{line}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A call being made.
+#[derive(Debug)]
+struct Frame {
+    function: usize,
+    start: Instant,
+    /// Time spent in the calls it made.
+    callees: Duration,
+}
+
+#[derive(Debug)]
+struct State {
+    functions: Vec<Function>,
+    index: HashMap<(String, Option<usize>), usize>,
+    stack: Vec<Frame>,
+    /// Time spent in the innermost function of each call stack, which is
+    /// given by the functions from the outermost.
+    stacks: HashMap<Vec<usize>, Duration>,
+    start: Instant,
+    /// Time spent in the calls made at the top level.
+    callees: Duration,
+}
+
+/// Hook timing the calls of every function, whether defined in lox or
+/// native.
+#[derive(Debug)]
+pub(crate) struct Profiler {
+    state: RefCell<State>,
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: RefCell::new(State {
+                functions: Vec::new(),
+                index: HashMap::new(),
+                stack: Vec::new(),
+                stacks: HashMap::new(),
+                start: Instant::now(),
+                callees: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Writes a row for each function, the most time spent in itself first.
+    pub(crate) fn write_table(&self, out: &mut dyn Write) {
+        let state = self.state.borrow();
+        let mut functions: Vec<_> = state.functions.iter().collect();
+        functions.sort_by(|a, b| {
+            b.exclusive
+                .cmp(&a.exclusive)
+                .then_with(|| a.label().cmp(&b.label()))
+        });
+
+        let width = functions
+            .iter()
+            .map(|function| function.label().len())
+            .chain(["Function".len()])
+            .max()
+            .unwrap();
+        writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>14}  {:>14}",
+            "Function", "Calls", "Inclusive (ms)", "Exclusive (ms)"
+        )
+        .unwrap();
+        for function in functions {
+            writeln!(
+                out,
+                "{:<width$}  {:>8}  {:>14.3}  {:>14.3}",
+                function.label(),
+                function.calls,
+                function.inclusive.as_secs_f64() * 1000.0,
+                function.exclusive.as_secs_f64() * 1000.0,
+            )
+            .unwrap();
+        }
+    }
+
+    /// Writes the call stacks in the folded format of flame graph tools,
+    /// e.g. `<script>;main:1;fib:5 120`: the functions from the outermost,
+    /// and the microseconds spent in the innermost.
+    pub(crate) fn write_folded(&self, out: &mut dyn Write) {
+        let state = self.state.borrow();
+        let script = state.start.elapsed().saturating_sub(state.callees);
+
+        let mut lines: Vec<_> = state
+            .stacks
+            .iter()
+            .map(|(stack, time)| {
+                let labels = stack.iter().map(|&i| state.functions[i].label());
+                let stack: Vec<_> = ["<script>".to_string()].into_iter().chain(labels).collect();
+                (stack.join(";"), time.as_micros())
+            })
+            .chain([("<script>".to_string(), script.as_micros())])
+            .collect();
+        lines.sort();
+
+        for (stack, micros) in lines {
+            writeln!(out, "{stack} {micros}").unwrap();
+        }
+    }
+}
+
+impl<'src> Hook<'src> for Profiler {
+    fn enter_call(&self, call: &Call, _arguments: &[LoxValue<'src>]) {
+        let state = &mut *self.state.borrow_mut();
+        let key = (call.name.to_string(), call.defined_at);
+        let function = *state.index.entry(key).or_insert_with(|| {
+            state.functions.push(Function {
+                name: call.name.to_string(),
+                defined_at: call.defined_at,
+                calls: 0,
+                inclusive: Duration::ZERO,
+                exclusive: Duration::ZERO,
+                active: 0,
+            });
+            state.functions.len() - 1
+        });
+
+        state.functions[function].calls += 1;
+        state.functions[function].active += 1;
+        state.stack.push(Frame {
+            function,
+            start: Instant::now(),
+            callees: Duration::ZERO,
+        });
+    }
+
+    fn leave_call(&self, _result: Option<&LoxValue<'src>>) {
+        let state = &mut *self.state.borrow_mut();
+        let Some(frame) = state.stack.pop() else {
+            return;
+        };
+        let elapsed = frame.start.elapsed();
+        let exclusive = elapsed.saturating_sub(frame.callees);
+
+        let mut stack: Vec<_> = state.stack.iter().map(|frame| frame.function).collect();
+        stack.push(frame.function);
+        *state.stacks.entry(stack).or_default() += exclusive;
+
+        let function = &mut state.functions[frame.function];
+        function.active -= 1;
+        function.exclusive += exclusive;
+        if function.active == 0 {
+            function.inclusive += elapsed;
+        }
+
+        match state.stack.last_mut() {
+            Some(caller) => caller.callees += elapsed,
+            None => state.callees += elapsed,
+        }
+    }
+}
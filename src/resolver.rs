@@ -0,0 +1,141 @@
+//! Gives every variable its [`Binding`] before the program runs, so that
+//! locals are found by slot instead of by name.
+
+use std::rc::Rc;
+
+use crate::{env::Binding, expr::ExprAst, interner::Interner, statement::StmtAst};
+
+/// The scopes mirror the environments made at runtime: one for each block
+/// and one for each function call, which holds the arguments and the body.
+/// The top level is global, and is not a scope.
+pub(crate) struct Resolver<'src, 'i> {
+    /// Names declared in each scope, in the order of their slots.
+    scopes: Vec<Vec<&'src str>>,
+    /// Of the run, to give globals their symbols.
+    interner: &'i mut Interner,
+}
+
+impl<'src, 'i> Resolver<'src, 'i> {
+    pub(crate) fn resolve(stmts: &mut [StmtAst<'src>], interner: &'i mut Interner) {
+        let mut resolver = Self {
+            scopes: Vec::new(),
+            interner,
+        };
+        for stmt in stmts {
+            resolver.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut StmtAst<'src>) {
+        match stmt {
+            StmtAst::Expression(v) => self.expr(&v.expr),
+            StmtAst::Print(v) => self.expr(&v.expr),
+            StmtAst::VarDecl(v) => {
+                // The initializer sees the outer variable of the same name.
+                if let Some(value) = &v.value {
+                    self.expr(value);
+                }
+                if let ExprAst::Variable(var) = &v.var {
+                    var.binding.set(self.declare(var.var.src));
+                }
+            }
+            StmtAst::Block(v) => {
+                self.scopes.push(Vec::new());
+                for stmt in &mut v.inner {
+                    self.stmt(stmt);
+                }
+                self.scopes.pop();
+            }
+            StmtAst::If(v) => {
+                self.expr(&v.condition);
+                self.stmt(&mut v.body);
+                if let Some(else_body) = &mut v.else_body {
+                    self.stmt(else_body);
+                }
+            }
+            StmtAst::While(v) => {
+                self.expr(&v.condition);
+                self.stmt(&mut v.body);
+            }
+            StmtAst::For(v) => {
+                if let Some(initializer) = &mut v.initializer {
+                    self.stmt(initializer);
+                }
+                if let Some(condition) = &v.condition {
+                    self.expr(condition);
+                }
+                if let Some(increment) = &v.increment {
+                    self.expr(increment);
+                }
+                self.stmt(&mut v.body);
+            }
+            StmtAst::FunctionDef(v) => {
+                // Not shared yet, as nothing has run.
+                let v = Rc::make_mut(v);
+                // Declared first, so that the body can call itself.
+                v.binding = self.declare(v.name.src);
+                let arguments = v.arguments.iter().map(|argument| argument.src).collect();
+                self.scopes.push(arguments);
+                for stmt in &mut v.body {
+                    self.stmt(stmt);
+                }
+                self.scopes.pop();
+            }
+            StmtAst::Return(v) => {
+                if let Some(expr) = &v.expr {
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &ExprAst<'src>) {
+        match expr {
+            ExprAst::Assign(v) => {
+                self.expr(v.value);
+                self.expr(v.assignee);
+            }
+            ExprAst::Binary(v) => {
+                self.expr(v.left);
+                self.expr(v.right);
+            }
+            ExprAst::FieldCall(v) => self.expr(v.object),
+            ExprAst::FunctionCall(v) => {
+                self.expr(v.callee);
+                for argument in &v.arguments {
+                    self.expr(argument);
+                }
+            }
+            ExprAst::Grouping(v) => self.expr(v.inner),
+            ExprAst::LiteralExpr(_) => {}
+            ExprAst::Unary(v) => self.expr(v.right),
+            ExprAst::Variable(v) => v.binding.set(self.lookup(v.var.src)),
+        }
+    }
+
+    /// Every declaration takes a new slot, even if the name is declared
+    /// again in the same scope.
+    fn declare(&mut self, name: &'src str) -> Binding {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.push(name);
+                Binding::Local {
+                    depth: 0,
+                    slot: scope.len() - 1,
+                }
+            }
+            None => Binding::Global(self.interner.intern(name)),
+        }
+    }
+
+    /// The latest declaration of the name, from the innermost scope. Names
+    /// not declared in any scope are global, even if they are not yet.
+    fn lookup(&mut self, name: &'src str) -> Binding {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(slot) = scope.iter().rposition(|declared| *declared == name) {
+                return Binding::Local { depth, slot };
+            }
+        }
+        Binding::Global(self.interner.intern(name))
+    }
+}
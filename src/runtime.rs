@@ -0,0 +1,162 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use crate::{
+    env::{Env, RuntimeError},
+    gc::Heap,
+    interner::Interner,
+    literal::LoxValue,
+    random::Rng,
+    statement::{If, StmtAst},
+};
+
+/// Function being called, as hooks see it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Call<'a> {
+    pub(crate) name: &'a str,
+    /// Line the function is defined at, or `None` for a native.
+    pub(crate) defined_at: Option<usize>,
+    /// Line of the call.
+    pub(crate) line: usize,
+}
+
+/// Observes a run, e.g. to debug it. Every method does nothing by default.
+pub(crate) trait Hook<'src> {
+    /// Called once the program is parsed, before it runs.
+    fn start(&self, _stmts: &[StmtAst<'src>]) {}
+
+    /// Called before each statement runs. An error stops the run.
+    fn before_stmt(
+        &self,
+        _stmt: &StmtAst<'src>,
+        _env: &Rc<RefCell<Env<'src>>>,
+        _stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    /// Called when a function is called, before its body runs.
+    fn enter_call(&self, _call: &Call, _arguments: &[LoxValue<'src>]) {}
+
+    /// Called when the last entered function returns, with `None` if it
+    /// fails.
+    fn leave_call(&self, _result: Option<&LoxValue<'src>>) {}
+
+    /// Called when a variable is declared or assigned at `line`.
+    fn assign(&self, _name: &str, _value: &LoxValue<'src>, _line: usize) {}
+
+    /// Called when the condition of an `if` is evaluated, with whether the
+    /// body is taken rather than the else branch.
+    fn branch(&self, _if_stmt: &If<'src>, _taken: bool) {}
+}
+
+/// Shared, so that the caller can read what the hook observed after the run.
+impl<'src, H: Hook<'src>> Hook<'src> for Rc<H> {
+    fn start(&self, stmts: &[StmtAst<'src>]) {
+        (**self).start(stmts);
+    }
+
+    fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        env: &Rc<RefCell<Env<'src>>>,
+        stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        (**self).before_stmt(stmt, env, stdout)
+    }
+
+    fn enter_call(&self, call: &Call, arguments: &[LoxValue<'src>]) {
+        (**self).enter_call(call, arguments);
+    }
+
+    fn leave_call(&self, result: Option<&LoxValue<'src>>) {
+        (**self).leave_call(result);
+    }
+
+    fn assign(&self, name: &str, value: &LoxValue<'src>, line: usize) {
+        (**self).assign(name, value, line);
+    }
+
+    fn branch(&self, if_stmt: &If<'src>, taken: bool) {
+        (**self).branch(if_stmt, taken);
+    }
+}
+
+/// State shared by every environment of a single run.
+pub(crate) struct Runtime<'src> {
+    pub(crate) rng: RefCell<Rng>,
+    pub(crate) interner: RefCell<Interner>,
+    pub(crate) heap: Heap<'src>,
+    pub(crate) hooks: Vec<Box<dyn Hook<'src> + 'src>>,
+}
+
+impl<'src> Runtime<'src> {
+    /// With a seed, random natives give the same values on every run.
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = seed.map_or_else(Rng::from_time, Rng::from_seed);
+        Self {
+            rng: RefCell::new(rng),
+            interner: RefCell::default(),
+            heap: Heap::default(),
+            hooks: Vec::new(),
+        }
+    }
+
+    /// Logs every collection of the heap.
+    pub fn with_gc_log(mut self) -> Self {
+        self.heap = self.heap.with_log();
+        self
+    }
+
+    pub fn with_hook(mut self, hook: impl Hook<'src> + 'src) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn start(&self, stmts: &[StmtAst<'src>]) {
+        for hook in &self.hooks {
+            hook.start(stmts);
+        }
+    }
+
+    pub fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        env: &Rc<RefCell<Env<'src>>>,
+        stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        for hook in &self.hooks {
+            hook.before_stmt(stmt, env, stdout)?;
+        }
+        Ok(())
+    }
+
+    pub fn enter_call(&self, call: &Call, arguments: &[LoxValue<'src>]) {
+        for hook in &self.hooks {
+            hook.enter_call(call, arguments);
+        }
+    }
+
+    pub fn leave_call(&self, result: Option<&LoxValue<'src>>) {
+        for hook in &self.hooks {
+            hook.leave_call(result);
+        }
+    }
+
+    pub fn assign(&self, name: &str, value: &LoxValue<'src>, line: usize) {
+        for hook in &self.hooks {
+            hook.assign(name, value, line);
+        }
+    }
+
+    pub fn branch(&self, if_stmt: &If<'src>, taken: bool) {
+        for hook in &self.hooks {
+            hook.branch(if_stmt, taken);
+        }
+    }
+}
+
+impl Default for Runtime<'_> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
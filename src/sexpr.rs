@@ -0,0 +1,83 @@
+//! Statements as S-expressions for 'parse --program', e.g.
+//! `(if (> x 1.0) (print x))`.
+//!
+//! Expressions are written as 'parse' writes them, except that a variable
+//! is its name, and calls and field accesses are S-expressions too, e.g.
+//! `(call f a)` and `(. a b)`, so that every node is one.
+
+use crate::{expr::ExprAst, statement::StmtAst};
+
+pub(crate) fn stmt(stmt: &StmtAst) -> String {
+    match stmt {
+        StmtAst::Expression(v) => format!("(expr {})", expr(&v.expr)),
+        StmtAst::Print(v) => format!("(print {})", expr(&v.expr)),
+        StmtAst::VarDecl(v) => match &v.value {
+            Some(value) => format!("(var {} {})", expr(&v.var), expr(value)),
+            None => format!("(var {})", expr(&v.var)),
+        },
+        StmtAst::Block(v) => block(&v.inner),
+        StmtAst::If(v) => match &v.else_body {
+            Some(else_body) => format!(
+                "(if {} {} {})",
+                expr(&v.condition),
+                self::stmt(&v.body),
+                self::stmt(else_body)
+            ),
+            None => format!("(if {} {})", expr(&v.condition), self::stmt(&v.body)),
+        },
+        StmtAst::While(v) => format!("(while {} {})", expr(&v.condition), self::stmt(&v.body)),
+        // Missing clauses are written as `_`, e.g. `(for _ _ _ (block))`.
+        StmtAst::For(v) => format!(
+            "(for {} {} {} {})",
+            v.initializer.as_deref().map_or("_".to_string(), self::stmt),
+            v.condition.as_ref().map_or("_".to_string(), expr),
+            v.increment.as_ref().map_or("_".to_string(), expr),
+            self::stmt(&v.body)
+        ),
+        // e.g. `(fun add (a b) (block (return (+ a b))))`.
+        StmtAst::FunctionDef(v) => {
+            let arguments: Vec<_> = v.arguments.iter().map(|argument| argument.src).collect();
+            format!(
+                "(fun {} ({}) {})",
+                v.name.src,
+                arguments.join(" "),
+                block(&v.body)
+            )
+        }
+        StmtAst::Return(v) => match &v.expr {
+            Some(value) => format!("(return {})", expr(value)),
+            None => "(return)".to_string(),
+        },
+    }
+}
+
+fn block(stmts: &[StmtAst]) -> String {
+    let mut out = "(block".to_string();
+    for stmt in stmts {
+        out.push(' ');
+        out.push_str(&self::stmt(stmt));
+    }
+    out.push(')');
+    out
+}
+
+fn expr(expr: &ExprAst) -> String {
+    match expr {
+        ExprAst::Assign(v) => format!("(= {} {})", self::expr(v.assignee), self::expr(v.value)),
+        ExprAst::Binary(v) => format!("({} {} {})", v.op, self::expr(v.left), self::expr(v.right)),
+        ExprAst::FieldCall(v) => format!("(. {} {})", self::expr(v.object), v.field),
+        ExprAst::FunctionCall(v) => {
+            let mut out = format!("(call {}", self::expr(v.callee));
+            for argument in &v.arguments {
+                out.push(' ');
+                out.push_str(&self::expr(argument));
+            }
+            out.push(')');
+            out
+        }
+        ExprAst::Grouping(v) => format!("(group {})", self::expr(v.inner)),
+        ExprAst::LiteralExpr(v) => v.to_string(),
+        ExprAst::Unary(v) => format!("({} {})", v.op, self::expr(v.right)),
+        ExprAst::Variable(v) => v.var.src.to_string(),
+    }
+}
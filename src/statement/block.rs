@@ -7,8 +7,10 @@ use crate::{env::Runnable, error::LoxError, literal::LoxValue, mac::tt, Env};
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Block<'src> {
     pub(crate) inner: Vec<StmtAst<'src>>,
+    /// the line of start of brace.
+    pub(crate) start_line: usize,
     /// the line of end of brace.
-    line: usize,
+    pub(crate) line: usize,
 }
 
 impl<'src> Runnable<'src> for Block<'src> {
@@ -35,15 +37,28 @@ impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_block(&mut self) -> Result<Block<'src>, StmtParseError> {
         let mut inner = Vec::new();
 
-        self.token_stream.next(); // Consume '{'.
+        let start_line = self.token_stream.next().line; // Consume '{'.
         while self.token_stream.peek().token_type != tt!("}") {
-            let next_stmt = self.parse()?;
-            inner.push(next_stmt);
+            match self.parse() {
+                Ok(next_stmt) => inner.push(next_stmt),
+                Err(err) if self.recovered.is_none() => return Err(err),
+                Err(err) => {
+                    // Closed by the brace the statement failed at, or never.
+                    if self.recover(err) || self.token_stream.expired() {
+                        return Ok(Block {
+                            inner,
+                            start_line,
+                            line: self.token_stream.line(),
+                        });
+                    }
+                }
+            }
         }
 
         match self.token_stream.expect(tt!("}")) {
             Ok(end_brace) => Ok(Block {
                 inner,
+                start_line,
                 line: end_brace.line,
             }),
             Err(unexpected_token) => Err(StmtParseError::ExpectedEndOfBracket(
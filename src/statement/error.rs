@@ -1,5 +1,3 @@
-use std::process::ExitCode;
-
 use crate::{error::IntoLoxError, expr::ExprParseError, mac::impl_from};
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -43,8 +41,28 @@ pub(crate) enum StmtParseError {
 
 impl_from!(StmtParseError: ExprParseError);
 
+impl StmtParseError {
+    /// Stable identifier of the error, e.g. for JSON output.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::ExprParseError(err) => err.code(),
+            Self::ExpectedSemicolon(_) => "expected-semicolon",
+            Self::InvalidVarDecl(_) => "invalid-var-decl",
+            Self::ExpectedEndOfBracket(_) => "expected-closing-brace",
+            Self::ExpectedOpeningParentheses(_) => "expected-opening-parenthesis",
+            Self::ExpectedClosingParentheses(_) => "expected-closing-parenthesis",
+            Self::InvalidForStmtInitializer(_) => "invalid-for-initializer",
+            Self::InvalidForStmtBody(_) => "invalid-for-body",
+            Self::InvalidWhileStmtBody(_) => "invalid-while-body",
+            Self::ExpectedIdent(_) => "expected-identifier",
+            Self::InvalidFunctionArgument(_) => "invalid-function-parameter",
+            Self::ExpectedBodyOfFunction => "expected-function-body",
+        }
+    }
+}
+
 impl IntoLoxError for StmtParseError {
-    fn exit_code(&self) -> ExitCode {
-        ExitCode::from(65)
+    fn exit_status(&self) -> u8 {
+        65
     }
 }
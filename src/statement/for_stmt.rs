@@ -8,10 +8,12 @@ use crate::{
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Clone, Debug, PartialEq)]
 pub struct For<'src> {
-    initializer: Option<Box<StmtAst<'src>>>,
-    condition: Option<ExprAst<'src>>,
-    increment: Option<ExprAst<'src>>,
-    body: Box<StmtAst<'src>>,
+    pub(crate) initializer: Option<Box<StmtAst<'src>>>,
+    pub(crate) condition: Option<ExprAst<'src>>,
+    pub(crate) increment: Option<ExprAst<'src>>,
+    pub(crate) body: Box<StmtAst<'src>>,
+    /// the line of 'for' token.
+    pub(crate) start_line: usize,
 }
 
 impl<'src> Runnable<'src> for For<'src> {
@@ -25,6 +27,7 @@ impl<'src> Runnable<'src> for For<'src> {
             condition,
             increment,
             body,
+            ..
         } = self;
 
         if let Some(init) = initializer {
@@ -34,7 +37,7 @@ impl<'src> Runnable<'src> for For<'src> {
         loop {
             if let Some(condition) = condition.as_ref() {
                 let value = condition.eval(env.clone(), stdout)?;
-                if !value.is_literal_and(|l| l.is_truthy()) {
+                if !value.is_truthy() {
                     break;
                 }
             }
@@ -58,7 +61,7 @@ impl<'src> Runnable<'src> for For<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_for(&mut self) -> Result<For<'src>, StmtParseError> {
-        self.token_stream.next(); // Consume 'for'.
+        let start_line = self.token_stream.next().line; // Consume 'for'.
         self.expect_opening_paren()?;
 
         let initializer = if self.token_stream.peek().token_type == tt!(";") {
@@ -108,6 +111,7 @@ impl<'src> StmtParser<'src, '_> {
             condition,
             increment,
             body,
+            start_line,
         })
     }
 }
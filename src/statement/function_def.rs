@@ -6,20 +6,30 @@ use super::{
     StmtParser,
 };
 use crate::{
-    env::RuntimeError, error::LoxError, function::LoxFunction, literal::LoxValue, mac::tt, Env,
-    Runnable,
+    env::{Binding, RuntimeError},
+    error::LoxError,
+    function::LoxFunction,
+    literal::LoxValue,
+    mac::tt,
+    token::Token,
+    Env, Runnable,
 };
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct FunctionDef<'src> {
-    pub(crate) name: String,
-    pub(crate) arguments: Vec<String>,
+    pub(crate) name: Token<'src>,
+    /// Where the name is defined, given by the resolver.
+    pub(crate) binding: Binding,
+    pub(crate) arguments: Vec<Token<'src>>,
     pub(crate) body: Vec<StmtAst<'src>>,
+    /// the line of 'fun' token.
+    pub(crate) start_line: usize,
     // end of body's bracket
-    line: usize,
+    pub(crate) line: usize,
 }
 
-impl<'src> Runnable<'src> for FunctionDef<'src> {
+/// Takes the `Rc`, so that every function defined by it shares the body.
+impl<'src> Runnable<'src> for Rc<FunctionDef<'src>> {
     fn run<W: Write>(
         &self,
         env: Rc<RefCell<Env<'src>>>,
@@ -30,7 +40,8 @@ impl<'src> Runnable<'src> for FunctionDef<'src> {
             closure: env.clone(),
         }
         .into();
-        env.borrow_mut().set(&self.name, lox_function);
+        env.borrow_mut()
+            .define(self.binding, self.name.src, lox_function);
         Ok(None)
     }
 
@@ -41,7 +52,7 @@ impl<'src> Runnable<'src> for FunctionDef<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_function_def(&mut self) -> Result<FunctionDef<'src>, StmtParseError> {
-        self.token_stream.next(); // Consume 'fun'.
+        let start_line = self.token_stream.next().line; // Consume 'fun'.
         let name = self.expect_identifier()?;
 
         let mut arguments = Vec::new();
@@ -70,15 +81,17 @@ impl<'src> StmtParser<'src, '_> {
 
         Ok(FunctionDef {
             name,
+            binding: Binding::Unresolved,
             arguments,
             body,
+            start_line,
             line,
         })
     }
 
-    fn expect_identifier(&mut self) -> Result<String, StmtParseError> {
+    fn expect_identifier(&mut self) -> Result<Token<'src>, StmtParseError> {
         match self.token_stream.expect(tt!("identifier")) {
-            Ok(token) => Ok(token.src.to_string()),
+            Ok(token) => Ok(token),
             Err(unexpected_token) => Err(ExpectedIdent(unexpected_token.src.to_string())),
         }
     }
@@ -8,9 +8,11 @@ use crate::{
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct If<'src> {
-    condition: ExprAst<'src>,
-    body: Box<StmtAst<'src>>,
-    else_body: Option<Box<StmtAst<'src>>>,
+    pub(crate) condition: ExprAst<'src>,
+    pub(crate) body: Box<StmtAst<'src>>,
+    pub(crate) else_body: Option<Box<StmtAst<'src>>>,
+    /// the line of 'if' token.
+    pub(crate) start_line: usize,
 }
 
 impl<'src> Runnable<'src> for If<'src> {
@@ -23,11 +25,16 @@ impl<'src> Runnable<'src> for If<'src> {
             condition,
             body,
             else_body,
+            ..
         } = self;
 
-        let condition_value = condition.eval(env.clone(), stdout)?;
+        let taken = condition.eval(env.clone(), stdout)?.is_truthy();
+        let runtime = env.borrow().hooked_runtime();
+        if let Some(runtime) = runtime {
+            runtime.branch(self, taken);
+        }
 
-        if condition_value.is_literal_and(|l| l.is_truthy()) {
+        if taken {
             if let Some(value) = body.run(env.clone(), stdout)? {
                 return Ok(Some(value));
             }
@@ -51,7 +58,7 @@ impl<'src> Runnable<'src> for If<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_if(&mut self) -> Result<If<'src>, StmtParseError> {
-        self.token_stream.next(); // Consume if.
+        let start_line = self.token_stream.next().line; // Consume if.
         self.expect_opening_paren()?;
         let condition = self.parse_following_expression()?;
         self.expect_closing_paren()?;
@@ -67,6 +74,7 @@ impl<'src> StmtParser<'src, '_> {
             condition,
             body,
             else_body,
+            start_line,
         })
     }
 }
@@ -19,7 +19,7 @@ pub(crate) use self::{
 use crate::{
     env::{Env, Runnable, RuntimeError},
     error::{IntoLoxError, LoxError},
-    expr::{ExprAst, ExprParser},
+    expr::{ExprArena, ExprAst, ExprParser},
     literal::LoxValue,
     mac::{impl_from, tt},
     TokenStream,
@@ -36,7 +36,8 @@ pub(crate) enum StmtAst<'src> {
     If(If<'src>),
     While(While<'src>),
     For(For<'src>),
-    FunctionDef(FunctionDef<'src>),
+    /// Shared with the functions it defines, not to copy the body.
+    FunctionDef(Rc<FunctionDef<'src>>),
     Return(Return<'src>),
 }
 
@@ -46,6 +47,13 @@ impl<'src> Runnable<'src> for StmtAst<'src> {
         env: Rc<RefCell<Env<'src>>>,
         stdout: &mut W,
     ) -> Result<Option<LoxValue<'src>>, LoxError<RuntimeError>> {
+        let runtime = env.borrow().hooked_runtime();
+        if let Some(runtime) = runtime {
+            runtime
+                .before_stmt(self, &env, stdout)
+                .map_err(|err| err.at(self.start_line()))?;
+        }
+
         match self {
             Self::Print(print) => print.run(env, stdout),
             Self::Expression(expression) => expression.run(env, stdout),
@@ -74,16 +82,63 @@ impl<'src> Runnable<'src> for StmtAst<'src> {
     }
 }
 
-impl_from!('src StmtAst: Expression, Print, VarDecl, Block, If, While, For, FunctionDef, Return);
+impl StmtAst<'_> {
+    /// The line where the statement begins, as opposed to [`Runnable::line`]
+    /// which is mostly where it ends.
+    pub(crate) fn start_line(&self) -> usize {
+        match self {
+            Self::Print(v) => v.start_line,
+            Self::Expression(v) => v.expr.start_line(),
+            Self::VarDecl(v) => v.start_line,
+            Self::Block(v) => v.start_line,
+            Self::If(v) => v.start_line,
+            Self::While(v) => v.start_line,
+            Self::For(v) => v.start_line,
+            Self::FunctionDef(v) => v.start_line,
+            Self::Return(v) => v.line,
+        }
+    }
+
+    /// The line where the statement ends, found from its tokens alone.
+    pub(crate) fn end_line(&self) -> usize {
+        match self {
+            Self::Print(v) => v.expr.end_line(),
+            Self::Expression(v) => v.expr.end_line(),
+            Self::VarDecl(v) => v.value.as_ref().unwrap_or(&v.var).end_line(),
+            Self::Block(v) => v.line,
+            Self::If(v) => v.else_body.as_ref().unwrap_or(&v.body).end_line(),
+            Self::While(v) => v.body.end_line(),
+            Self::For(v) => v.body.end_line(),
+            Self::FunctionDef(v) => v.line,
+            Self::Return(v) => v.expr.as_ref().map_or(v.line, ExprAst::end_line),
+        }
+    }
+}
+
+impl_from!('src StmtAst: Expression, Print, VarDecl, Block, If, While, For, Return);
+
+impl<'src> From<FunctionDef<'src>> for StmtAst<'src> {
+    fn from(value: FunctionDef<'src>) -> Self {
+        Self::FunctionDef(Rc::new(value))
+    }
+}
 
 /// Parser for statement AST.
 pub(crate) struct StmtParser<'src, 'mr> {
     pub(crate) token_stream: &'mr mut TokenStream<'src>,
+    /// Where the expressions are allocated.
+    arena: &'src ExprArena<'src>,
+    /// Errors of the statements skipped, when parsing with recovery.
+    recovered: Option<Vec<LoxError<StmtParseError>>>,
 }
 
 impl<'src, 'mr> StmtParser<'src, 'mr> {
-    pub fn new(token_stream: &'mr mut TokenStream<'src>) -> Self {
-        StmtParser { token_stream }
+    pub fn new(token_stream: &'mr mut TokenStream<'src>, arena: &'src ExprArena<'src>) -> Self {
+        StmtParser {
+            token_stream,
+            arena,
+            recovered: None,
+        }
     }
 }
 
@@ -94,12 +149,65 @@ impl<'src> StmtParser<'src, '_> {
         while !self.token_stream.expired() {
             let stmt = self
                 .parse()
-                .map_err(|err| err.at(self.token_stream.line()))?;
+                .map_err(|err| err.at_token(&self.token_stream.last))?;
             statements.push(stmt);
         }
         Ok(statements)
     }
 
+    /// Parses whole source code, skipping the statements which do not
+    /// parse, e.g. of a document being edited. Returns the rest, and the
+    /// errors of those skipped.
+    pub(crate) fn parse_recovering(
+        mut self,
+    ) -> (Vec<StmtAst<'src>>, Vec<LoxError<StmtParseError>>) {
+        self.recovered = Some(Vec::new());
+        let mut statements = Vec::new();
+        while !self.token_stream.expired() {
+            match self.parse() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    self.recover(err);
+                    // No block is open for it to close.
+                    self.token_stream.eat(tt!("}"));
+                }
+            }
+        }
+        (statements, self.recovered.unwrap_or_default())
+    }
+
+    /// Records the error, and skips to the start of the next statement, or
+    /// to the end of the enclosing block. Returns whether the statement
+    /// failed at the closing brace of the block, which it then consumed.
+    fn recover(&mut self, err: StmtParseError) -> bool {
+        let err = err.at_token(&self.token_stream.last);
+        self.recovered.get_or_insert_with(Vec::new).push(err);
+        if self.token_stream.last.token_type == tt!("}") {
+            return true;
+        }
+        loop {
+            match self.token_stream.peek().token_type {
+                tt!("")
+                | tt!("}")
+                | tt!("var")
+                | tt!("fun")
+                | tt!("for")
+                | tt!("if")
+                | tt!("while")
+                | tt!("print")
+                | tt!("return") => break,
+                tt!(";") => {
+                    self.token_stream.next();
+                    break;
+                }
+                _ => {
+                    self.token_stream.next();
+                }
+            }
+        }
+        false
+    }
+
     /// Parses the following AST.
     pub(crate) fn parse(&mut self) -> Result<StmtAst<'src>, StmtParseError> {
         match self.token_stream.peek().token_type {
@@ -118,7 +226,7 @@ impl<'src> StmtParser<'src, '_> {
     /// Lent its own token stream's mutable reference to expression parser,
     /// and parse following expression.
     fn parse_following_expression(&mut self) -> Result<ExprAst<'src>, StmtParseError> {
-        ExprParser::new(self.token_stream)
+        ExprParser::new(self.token_stream, self.arena)
             .parse()
             .map_err(Into::into)
     }
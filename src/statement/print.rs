@@ -10,6 +10,8 @@ use crate::{
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct Print<'src> {
     pub(crate) expr: ExprAst<'src>,
+    /// the line of 'print' token.
+    pub(crate) start_line: usize,
 }
 
 impl<'src> Runnable<'src> for Print<'src> {
@@ -30,10 +32,10 @@ impl<'src> Runnable<'src> for Print<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_print(&mut self) -> Result<Print<'src>, StmtParseError> {
-        self.token_stream.next(); // consume the 'print' token.
+        let start_line = self.token_stream.next().line; // consume the 'print' token.
         let expr = self.parse_following_expression()?;
         self.expect_semicolon()?;
 
-        Ok(Print { expr })
+        Ok(Print { expr, start_line })
     }
 }
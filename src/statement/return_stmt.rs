@@ -14,8 +14,8 @@ use crate::{
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Return<'src> {
     pub(crate) expr: Option<ExprAst<'src>>,
-    /// return token's line, but not directly used.
-    line: usize,
+    /// return token's line.
+    pub(crate) line: usize,
 }
 
 impl<'src> Runnable<'src> for Return<'src> {
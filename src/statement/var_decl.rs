@@ -10,7 +10,6 @@ use crate::{
     expr::{Assign, ExprAst},
     literal::{Literal, LoxValue},
     statement::error::StmtParseError::{self, *},
-    token::Token,
     Env, Evaluatable,
 };
 
@@ -19,6 +18,8 @@ use crate::{
 pub(crate) struct VarDecl<'src> {
     pub(crate) var: ExprAst<'src>,
     pub(crate) value: Option<ExprAst<'src>>,
+    /// the line of 'var' token.
+    pub(crate) start_line: usize,
 }
 
 impl<'src> Runnable<'src> for VarDecl<'src> {
@@ -27,8 +28,8 @@ impl<'src> Runnable<'src> for VarDecl<'src> {
         env: Rc<RefCell<Env<'src>>>,
         stdout: &mut W,
     ) -> Result<Option<LoxValue<'src>>, LoxError<RuntimeError>> {
-        let var: Token<'src> = match &self.var {
-            ExprAst::Variable(variable) => Ok(variable.var.clone()),
+        let (var, binding) = match &self.var {
+            ExprAst::Variable(variable) => Ok((variable.var, variable.binding.get())),
             rest => Err(InvalidAssignmentTarget(rest.to_string()).at(self.line())),
         }?;
 
@@ -37,7 +38,11 @@ impl<'src> Runnable<'src> for VarDecl<'src> {
             None => Literal::Nil.into(),
         };
 
-        env.borrow_mut().set(var.src, value);
+        let runtime = env.borrow().hooked_runtime();
+        if let Some(runtime) = runtime {
+            runtime.assign(var.src, &value, self.line());
+        }
+        env.borrow_mut().define(binding, var.src, value);
         Ok(None)
     }
 
@@ -52,7 +57,7 @@ impl<'src> Runnable<'src> for VarDecl<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub fn parse_var_decl(&mut self) -> Result<VarDecl<'src>, StmtParseError> {
-        self.token_stream.next(); // consume the 'var' token.
+        let start_line = self.token_stream.next().line; // consume the 'var' token.
         let following = self.parse_following_expression()?;
 
         let result = match following {
@@ -60,11 +65,14 @@ impl<'src> StmtParser<'src, '_> {
             ExprAst::Variable(_) => Ok(VarDecl {
                 var: following,
                 value: None,
+                start_line,
             }),
             // e.g. var x = 1;
+            // Only the two nodes are copied, not what they refer to.
             ExprAst::Assign(Assign { assignee, value }) => Ok(VarDecl {
-                var: *assignee.clone(),
-                value: Some(*value.clone()),
+                var: assignee.clone(),
+                value: Some(value.clone()),
+                start_line,
             }),
             _ => Err(InvalidVarDecl(following.to_string())),
         };
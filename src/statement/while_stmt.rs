@@ -6,8 +6,10 @@ use crate::{env::Runnable, error::LoxError, expr::ExprAst, literal::LoxValue, En
 /// NOTE: lifetime 'a denotes the lifetime of source code.
 #[derive(Debug, Clone, PartialEq)]
 pub struct While<'src> {
-    condition: ExprAst<'src>,
-    body: Box<StmtAst<'src>>,
+    pub(crate) condition: ExprAst<'src>,
+    pub(crate) body: Box<StmtAst<'src>>,
+    /// the line of 'while' token.
+    pub(crate) start_line: usize,
 }
 
 impl<'src> Runnable<'src> for While<'src> {
@@ -16,12 +18,11 @@ impl<'src> Runnable<'src> for While<'src> {
         env: Rc<RefCell<Env<'src>>>,
         stdout: &mut W,
     ) -> Result<Option<LoxValue<'src>>, LoxError<RuntimeError>> {
-        let While { condition, body } = self;
+        let While {
+            condition, body, ..
+        } = self;
 
-        while condition
-            .eval(env.clone(), stdout)?
-            .is_literal_and(|l| l.is_truthy())
-        {
+        while condition.eval(env.clone(), stdout)?.is_truthy() {
             if let Some(value) = body.run(env.clone(), stdout)? {
                 return Ok(Some(value));
             }
@@ -37,7 +38,7 @@ impl<'src> Runnable<'src> for While<'src> {
 
 impl<'src> StmtParser<'src, '_> {
     pub(super) fn parse_while(&mut self) -> Result<While<'src>, StmtParseError> {
-        self.token_stream.next(); // Consume 'while'
+        let start_line = self.token_stream.next().line; // Consume 'while'
         self.expect_opening_paren()?;
         let condition = self.parse_following_expression()?;
         self.expect_closing_paren()?;
@@ -53,6 +54,10 @@ impl<'src> StmtParser<'src, '_> {
             rest => return Err(StmtParseError::InvalidWhileStmtBody(format!("{rest:?}"))),
         };
 
-        Ok(While { condition, body })
+        Ok(While {
+            condition,
+            body,
+            start_line,
+        })
     }
 }
@@ -1,5 +1,3 @@
-use std::process::ExitCode;
-
 use crate::error::IntoLoxError;
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -10,8 +8,18 @@ pub enum TokenizeError {
     UnterminatedString,
 }
 
+impl TokenizeError {
+    /// Stable identifier of the error, e.g. for JSON output.
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedCharacter(_) => "unexpected-character",
+            Self::UnterminatedString => "unterminated-string",
+        }
+    }
+}
+
 impl IntoLoxError for TokenizeError {
-    fn exit_code(&self) -> ExitCode {
-        ExitCode::from(65)
+    fn exit_status(&self) -> u8 {
+        65
     }
 }
@@ -1,5 +1,4 @@
 mod error;
-mod regex;
 #[allow(clippy::module_inception)]
 mod token;
 mod token_stream;
@@ -2,120 +2,151 @@ use std::fmt;
 
 use crate::{literal::Number, mac::tt};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) struct Token<'a> {
     /// The reference to the actual source.
     /// Be careful with [`TokenType::String`], because it contains the quotes.
     /// e.g) \"Hello, World!\"
     /// It is used for translating literal tokens to its own value.
     pub line: usize,
+    /// Counted in characters from 1, where the token starts.
+    pub column: usize,
     pub src: &'a str,
     pub token_type: TokenType,
 }
 
 impl<'a> Token<'a> {
     /// Create reserved word tokens or identifiers.
-    pub(crate) fn word(line: usize, src: &'a str) -> Self {
+    pub(crate) fn word(line: usize, column: usize, src: &'a str) -> Self {
         match src {
             "and" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("and"),
             },
             "class" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("class"),
             },
             "else" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("else"),
             },
             "false" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("false"),
             },
             "fun" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("fun"),
             },
             "for" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("for"),
             },
             "if" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("if"),
             },
             "nil" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("nil"),
             },
             "or" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("or"),
             },
             "print" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("print"),
             },
             "return" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("return"),
             },
             "super" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("super"),
             },
             "this" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("this"),
             },
             "true" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("true"),
             },
             "var" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("var"),
             },
             "while" => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("while"),
             },
             _ => Token {
                 line,
+                column,
                 src,
                 token_type: tt!("identifier"),
             },
         }
     }
 
-    pub(crate) fn number(line: usize, src: &'a str) -> Self {
+    pub(crate) fn number(line: usize, column: usize, src: &'a str) -> Self {
         Token {
             line,
+            column,
             src,
             token_type: tt!("number"),
         }
     }
 
-    pub(crate) fn string(line: usize, src: &'a str) -> Self {
+    /// The src contains the leading '//', but not the trailing newline.
+    pub(crate) fn comment(line: usize, column: usize, src: &'a str) -> Self {
         Token {
             line,
+            column,
+            src,
+            token_type: tt!("comment"),
+        }
+    }
+
+    pub(crate) fn string(line: usize, column: usize, src: &'a str) -> Self {
+        Token {
+            line,
+            column,
             src,
             token_type: tt!("string"),
         }
@@ -182,6 +213,7 @@ pub(crate) enum TokenType {
     True,
     Var,
     While,
+    Comment,
     Eof,
 }
 
@@ -227,6 +259,7 @@ impl fmt::Debug for TokenType {
             Self::True => write!(f, "TRUE"),
             Self::Var => write!(f, "VAR"),
             Self::While => write!(f, "WHILE"),
+            Self::Comment => write!(f, "COMMENT"),
             Self::Eof => write!(f, "EOF"),
         }
     }
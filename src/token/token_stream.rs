@@ -1,50 +1,56 @@
-use super::{Token, TokenType};
+use super::{Token, TokenType, TokenizeError};
+use crate::error::LoxError;
 
-enum TokenStreamState<'a> {
-    /// Index of the next token to be returned.
-    NotExpired(usize),
-    /// The stream has expired, and the last token(eof) is stored here.
-    Expired(&'a Token<'a>),
-}
+type TokenResult<'a> = Result<Token<'a>, LoxError<TokenizeError>>;
 
+/// Tokens for the parsers, pulled from the tokenizer one at a time, so that
+/// only the next token is held ahead of the parser.
+///
+/// If tokenizing fails, the stream ends there as if eof was reached, and the
+/// error is kept to be returned by [`TokenStream::finish`].
 pub struct TokenStream<'a> {
-    tokens: &'a [Token<'a>],
+    tokens: Box<dyn Iterator<Item = TokenResult<'a>> + 'a>,
+    /// The token to be returned next. Once it is eof, it stays.
+    peeked: Token<'a>,
+    error: Option<LoxError<TokenizeError>>,
     pub line: usize,
-    state: TokenStreamState<'a>,
+    /// The last token returned, e.g. where a parser failed.
+    pub(crate) last: Token<'a>,
 }
 
 impl<'a> TokenStream<'a> {
-    pub fn new(tokens: &'a [Token<'a>]) -> Self {
-        Self {
-            tokens,
-            state: TokenStreamState::NotExpired(0),
+    /// The tokens must end with eof, as [`Tokenizer`](super::Tokenizer)
+    /// does.
+    pub fn new(tokens: impl IntoIterator<Item = TokenResult<'a>> + 'a) -> Self {
+        let mut stream = Self {
+            tokens: Box::new(tokens.into_iter()),
+            peeked: eof(0),
+            error: None,
             line: 0,
-        }
+            last: eof(0),
+        };
+        stream.peeked = stream.pull();
+        stream
     }
 
     /// Get the next token.
-    pub fn next(&mut self) -> &'a Token<'a> {
-        match self.state {
-            TokenStreamState::NotExpired(index) => {
-                let token = &self.tokens[index];
-                self.line = token.line;
-                self.set_state(index + 1);
-                token
-            }
-            TokenStreamState::Expired(token) => token,
+    pub fn next(&mut self) -> Token<'a> {
+        let token = self.peeked;
+        if token.token_type != TokenType::Eof {
+            self.peeked = self.pull();
         }
+        self.line = token.line;
+        self.last = token;
+        token
     }
 
-    pub fn peek(&self) -> &'a Token<'a> {
-        match self.state {
-            TokenStreamState::NotExpired(index) => &self.tokens[index],
-            TokenStreamState::Expired(token) => token,
-        }
+    pub fn peek(&self) -> Token<'a> {
+        self.peeked
     }
 
     /// Expect the next token to be of a certain type.
     /// If it is, return Ok(token) else return Err(token).
-    pub fn expect(&mut self, expected: TokenType) -> Result<&'a Token<'a>, &'a Token<'a>> {
+    pub fn expect(&mut self, expected: TokenType) -> Result<Token<'a>, Token<'a>> {
         let token = self.next();
         if token.token_type == expected {
             Ok(token)
@@ -62,26 +68,45 @@ impl<'a> TokenStream<'a> {
         }
     }
 
+    /// Check if the next token is EOF.
     pub fn expired(&self) -> bool {
-        match self.state {
-            TokenStreamState::NotExpired(index) => {
-                // Check if the next token is EOF
-                let next_token = &self.tokens[index];
-                next_token.token_type == TokenType::Eof
-            }
-            TokenStreamState::Expired(_) => true,
-        }
+        self.peeked.token_type == TokenType::Eof
     }
 
     pub fn line(&self) -> usize {
         self.line
     }
 
-    fn set_state(&mut self, index: usize) {
-        if index < self.tokens.len() {
-            self.state = TokenStreamState::NotExpired(index);
-        } else {
-            self.state = TokenStreamState::Expired(&self.tokens[self.tokens.len() - 1]);
+    /// Tokenizes the rest of the source, and returns the first error of
+    /// tokenizing if any. The parsers stop at the first error, whereas errors
+    /// of tokenizing are reported before them, even if they come later in the
+    /// source.
+    pub(crate) fn finish(mut self) -> Result<(), LoxError<TokenizeError>> {
+        while !self.expired() {
+            self.next();
         }
+        self.error.map_or(Ok(()), Err)
+    }
+
+    fn pull(&mut self) -> Token<'a> {
+        match self.tokens.next() {
+            Some(Ok(token)) => token,
+            Some(Err(err)) => {
+                let token = eof(err.line);
+                self.error = Some(err);
+                token
+            }
+            // Should be unreachable, as eof is the last token.
+            None => eof(self.line),
+        }
+    }
+}
+
+fn eof(line: usize) -> Token<'static> {
+    Token {
+        line,
+        column: 1,
+        src: "",
+        token_type: TokenType::Eof,
     }
 }
@@ -1,10 +1,6 @@
-use regex::Regex;
+use std::ops::Range;
 
 use super::{
-    regex::{
-        COMMENT_REGEX, NUMBER_REGEX, RAW_STRING_REGEX, UNTERMINATED_STRING_REGEX, WHITESPACE_REGEX,
-        WORD_REGEX,
-    },
     token::Token,
     TokenType,
     TokenizeError::{
@@ -20,63 +16,114 @@ pub(crate) struct Tokenizer<'a> {
     src: &'a str,
     pos: usize,
     line: usize,
+    /// Where the token being tokenized starts.
+    start: usize,
+    /// Whether comments are returned as tokens instead of being skipped.
+    keep_comments: bool,
+    /// Whether eof has been returned.
+    done: bool,
+}
+
+/// Tokens are scanned one at a time as they are asked for, until eof, which
+/// is the last item. Errors are returned in place of the token which could
+/// not be scanned, and the tokenizer goes on after them.
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, LoxError<TokenizeError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let token = self
+            .next_token()
+            .map_err(|err| err.at_columns(self.line, self.error_columns()));
+        self.done = matches!(
+            token,
+            Ok(Token {
+                token_type: tt!(""),
+                ..
+            })
+        );
+        Some(token)
+    }
 }
 
 impl<'a> Tokenizer<'a> {
     pub(crate) fn new(src: &'a str) -> Self {
+        // Skip the shebang line (e.g. `#!/usr/bin/env lox`), but leave its
+        // newline so that line numbers stay the same.
+        let pos = if src.starts_with("#!") {
+            src.find('\n').unwrap_or(src.len())
+        } else {
+            0
+        };
+
         Self {
             src,
-            pos: 0,
+            pos,
             line: 1,
+            start: pos,
+            keep_comments: false,
+            done: false,
         }
     }
 
-    /// Wrap the result of `next_token` with the current line number,
-    /// and collect them until eof is returned.
-    pub(crate) fn tokenize(&mut self) -> Vec<Result<Token<'a>, LoxError<TokenizeError>>> {
-        let mut tokens = Vec::new();
-
-        loop {
-            let token = self.next_token_with_line();
-            match token.as_ref() {
-                Ok(Token {
-                    token_type: tt!(""),
-                    ..
-                }) => {
-                    tokens.push(token);
-                    break;
-                }
-                _ => tokens.push(token),
-            }
+    /// Same as `new`, but comments are kept as [`TokenType::Comment`] tokens,
+    /// e.g. for the formatter.
+    pub(crate) fn with_comments(src: &'a str) -> Self {
+        Self {
+            keep_comments: true,
+            ..Self::new(src)
         }
-
-        tokens
-    }
-
-    fn next_token_with_line(&mut self) -> Result<Token<'a>, LoxError<TokenizeError>> {
-        self.next_token().map_err(|e| e.at(self.line))
     }
 
+    /// Scans the next token, skipping whitespaces and comments (unless they
+    /// are kept) before it.
     fn next_token(&mut self) -> Result<Token<'a>, TokenizeError> {
-        let token = if self.consume_match(&COMMENT_REGEX).is_some() {
-            // If we find a comment, we skip it and continue to the next token.
-            self.next_token()?
-        } else if self.consume_match(&WHITESPACE_REGEX).is_some() {
-            // If we find a whitespace, we skip it and continue to the next token.
-            self.next_token()?
-        } else if let Some(src) = self.consume_match(&RAW_STRING_REGEX) {
-            Token::string(self.line, src)
-        } else if self.consume_match(&UNTERMINATED_STRING_REGEX).is_some() {
-            // It is confirmed that the string doesn't have a closing quote,
-            // which is not determined by [`UNTERMINATED_STRING_REGEX`], but by
-            // [`RAW_STRING_REGEX`] above.
-            return Err(UnterminatedString);
-        } else if let Some(src) = self.consume_match(&NUMBER_REGEX) {
-            Token::number(self.line, src)
-        } else if let Some(src) = self.consume_match(&WORD_REGEX) {
-            Token::word(self.line, src)
-        } else if let Some(ch) = self.advance() {
-            match ch {
+        loop {
+            let line = self.line;
+            self.start = self.pos;
+            let Some(ch) = self.advance() else {
+                // We must be at the end of the file.
+                return Ok(self.token("", tt!("")));
+            };
+
+            let token = match ch {
+                ch if ch.is_whitespace() => {
+                    self.advance_while(char::is_whitespace);
+                    continue;
+                }
+                '/' if self.remain().starts_with('/') => {
+                    self.advance_while(|ch| ch != '\n');
+                    if !self.keep_comments {
+                        continue;
+                    }
+                    Token::comment(line, self.column(), self.lexeme().trim_end())
+                }
+                '"' => {
+                    self.advance_while(|ch| ch != '"');
+                    if self.advance().is_none() {
+                        return Err(UnterminatedString);
+                    }
+                    // The line is where the string ends.
+                    Token::string(self.line, self.column(), self.lexeme())
+                }
+                '0'..='9' => {
+                    self.advance_while(|ch| ch.is_ascii_digit());
+                    // The fractional part needs a digit after the '.'.
+                    let mut rest = self.remain().chars();
+                    if rest.next() == Some('.') && rest.next().is_some_and(|ch| ch.is_ascii_digit())
+                    {
+                        self.advance();
+                        self.advance_while(|ch| ch.is_ascii_digit());
+                    }
+                    Token::number(self.line, self.column(), self.lexeme())
+                }
+                'a'..='z' | 'A'..='Z' | '_' => {
+                    self.advance_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+                    Token::word(self.line, self.column(), self.lexeme())
+                }
                 '(' => self.token("(", tt!("(")),
                 ')' => self.token(")", tt!(")")),
                 '{' => self.token("{", tt!("{")),
@@ -87,56 +134,19 @@ impl<'a> Tokenizer<'a> {
                 '+' => self.token("+", tt!("+")),
                 ';' => self.token(";", tt!(";")),
                 '*' => self.token("*", tt!("*")),
-                '=' => {
-                    if self.remain().starts_with('=') {
-                        self.advance();
-                        self.token("==", tt!("=="))
-                    } else {
-                        self.token("=", tt!("="))
-                    }
-                }
-                '!' => {
-                    if self.remain().starts_with('=') {
-                        self.advance();
-                        self.token("!=", tt!("!="))
-                    } else {
-                        self.token("!", tt!("!"))
-                    }
-                }
-                '>' => {
-                    if self.remain().starts_with('=') {
-                        self.advance();
-                        self.token(">=", tt!(">="))
-                    } else {
-                        self.token(">", tt!(">"))
-                    }
-                }
-                '<' => {
-                    if self.remain().starts_with('=') {
-                        self.advance();
-                        self.token("<=", tt!("<="))
-                    } else {
-                        self.token("<", tt!("<"))
-                    }
-                }
                 '/' => self.token("/", tt!("/")),
+                '=' if self.eat('=') => self.token("==", tt!("==")),
+                '=' => self.token("=", tt!("=")),
+                '!' if self.eat('=') => self.token("!=", tt!("!=")),
+                '!' => self.token("!", tt!("!")),
+                '>' if self.eat('=') => self.token(">=", tt!(">=")),
+                '>' => self.token(">", tt!(">")),
+                '<' if self.eat('=') => self.token("<=", tt!("<=")),
+                '<' => self.token("<", tt!("<")),
                 ch => return Err(UnexpectedCharacter(ch)),
-            }
-        } else {
-            // None of the above, so we must be at the end of the file.
-            self.token("", tt!(""))
-        };
-
-        Ok(token)
-    }
-
-    /// Try match the regex from the current position in the src,
-    /// and consume the match if it exists.
-    fn consume_match(&mut self, regex: &Regex) -> Option<&'a str> {
-        let found = regex.find(self.remain()).map(|m| m.as_str())?;
-        self.pos += found.len();
-        self.line += found.chars().filter(|&c| c == '\n').count();
-        Some(found)
+            };
+            return Ok(token);
+        }
     }
 
     /// Consume a character from the src and return it.
@@ -149,6 +159,47 @@ impl<'a> Tokenizer<'a> {
         Some(c)
     }
 
+    /// Column of the start of the current token.
+    fn column(&self) -> usize {
+        self.column_at(self.start)
+    }
+
+    /// Columns of the token in error, or of its part on the last line if it
+    /// spans lines, as the line of the error is the last.
+    fn error_columns(&self) -> Range<usize> {
+        let start = match self.lexeme().contains('\n') {
+            true => 1,
+            false => self.column(),
+        };
+        start..self.column_at(self.pos)
+    }
+
+    fn column_at(&self, pos: usize) -> usize {
+        let line_start = self.src[..pos].rfind('\n').map_or(0, |i| i + 1);
+        self.src[line_start..pos].chars().count() + 1
+    }
+
+    /// Consume characters while they satisfy the predicate.
+    fn advance_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while self.remain().starts_with(&predicate) {
+            self.advance();
+        }
+    }
+
+    /// Consume the character if it is the expected one.
+    fn eat(&mut self, expected: char) -> bool {
+        let matched = self.remain().starts_with(expected);
+        if matched {
+            self.advance();
+        }
+        matched
+    }
+
+    /// The source of the current token so far.
+    fn lexeme(&self) -> &'a str {
+        &self.src[self.start..self.pos]
+    }
+
     fn remain(&self) -> &'a str {
         &self.src[self.pos..]
     }
@@ -156,6 +207,7 @@ impl<'a> Tokenizer<'a> {
     fn token(&self, src: &'a str, token_type: TokenType) -> Token<'a> {
         Token {
             line: self.line,
+            column: self.column(),
             src,
             token_type,
         }
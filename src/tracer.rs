@@ -0,0 +1,105 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use crate::{
+    env::{Env, RuntimeError},
+    literal::LoxValue,
+    runtime::{Call, Hook},
+    statement::StmtAst,
+};
+
+#[derive(Debug, Default)]
+struct State {
+    /// Name of each function being called, and whether it is filtered.
+    calls: Vec<(String, bool)>,
+    /// Calls of the filtered function being made.
+    filtered: usize,
+    /// Line and depth of the last statement, so that statements on the same
+    /// line, e.g. `if (x) return;`, are written once.
+    last: Option<(usize, usize)>,
+}
+
+/// Hook writing what the program does as it runs: each statement, each call
+/// with its arguments and what it returns, and each variable declared or
+/// assigned. Lines are indented by the depth of the calls.
+pub(crate) struct Tracer<'a, W: Write> {
+    lines: Vec<&'a str>,
+    /// If given, only the calls of the function of this name are traced,
+    /// along with everything they do.
+    filter: Option<&'a str>,
+    out: RefCell<&'a mut W>,
+    state: RefCell<State>,
+}
+
+impl<'a, W: Write> Tracer<'a, W> {
+    pub(crate) fn new(src: &'a str, filter: Option<&'a str>, out: &'a mut W) -> Self {
+        Self {
+            lines: src.lines().collect(),
+            filter,
+            out: RefCell::new(out),
+            state: RefCell::new(State::default()),
+        }
+    }
+
+    fn tracing(&self) -> bool {
+        self.filter.is_none() || self.state.borrow().filtered > 0
+    }
+
+    fn trace(&self, message: &str) {
+        let indent = "  ".repeat(self.state.borrow().calls.len());
+        writeln!(self.out.borrow_mut(), "{indent}{message}").unwrap();
+    }
+}
+
+impl<'src, W: Write> Hook<'src> for Tracer<'_, W> {
+    fn before_stmt(
+        &self,
+        stmt: &StmtAst<'src>,
+        _env: &Rc<RefCell<Env<'src>>>,
+        _stdout: &mut dyn Write,
+    ) -> Result<(), RuntimeError> {
+        let line = stmt.start_line();
+        let depth = self.state.borrow().calls.len();
+        let last = self.state.borrow_mut().last.replace((line, depth));
+        if self.tracing() && last != Some((line, depth)) {
+            let text = self.lines.get(line - 1).map_or("", |text| text.trim());
+            self.trace(&format!("[line {line}] {text}"));
+        }
+        Ok(())
+    }
+
+    fn enter_call(&self, call: &Call, arguments: &[LoxValue<'src>]) {
+        let filtered = self.filter == Some(call.name);
+        if filtered {
+            self.state.borrow_mut().filtered += 1;
+        }
+
+        if self.tracing() {
+            let arguments: Vec<_> = arguments.iter().map(LoxValue::inspect).collect();
+            self.trace(&format!("call {}({})", call.name, arguments.join(", ")));
+        }
+        let mut state = self.state.borrow_mut();
+        state.calls.push((call.name.to_string(), filtered));
+    }
+
+    fn leave_call(&self, result: Option<&LoxValue<'src>>) {
+        let Some((name, filtered)) = self.state.borrow_mut().calls.pop() else {
+            return;
+        };
+
+        if self.tracing() {
+            match result {
+                Some(value) => self.trace(&format!("{name} returned {}", value.inspect())),
+                None => self.trace(&format!("{name} failed")),
+            }
+        }
+        if filtered {
+            self.state.borrow_mut().filtered -= 1;
+        }
+    }
+
+    fn assign(&self, name: &str, value: &LoxValue<'src>, line: usize) {
+        if self.tracing() {
+            self.trace(&format!("[line {line}] {name} = {}", value.inspect()));
+        }
+    }
+}
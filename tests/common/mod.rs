@@ -0,0 +1,43 @@
+//! Fixtures shared by the integration tests, each of which uses some only.
+#![allow(dead_code, unused_macros)]
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_run_with, RunOptions};
+
+/// Calls the entry point with buffers for stdout and stderr, and returns the
+/// exit code and what is written to them.
+pub fn capture(
+    entry: impl FnOnce(&mut Vec<u8>, &mut Vec<u8>) -> ExitCode,
+) -> (ExitCode, String, String) {
+    let (mut ok_buf, mut err_buf) = (Vec::new(), Vec::new());
+    let exit_code = entry(&mut ok_buf, &mut err_buf);
+    (
+        exit_code,
+        String::from_utf8(ok_buf).unwrap(),
+        String::from_utf8(err_buf).unwrap(),
+    )
+}
+
+/// Runs the source, and returns the exit code, stdout and stderr.
+pub fn run_with(src: &str, options: &RunOptions) -> (ExitCode, String, String) {
+    capture(|ok_buf, err_buf| lox_run_with(src, options, ok_buf, err_buf))
+}
+
+/// Runs the source, and returns its stdout and stderr, expecting success.
+pub fn run_ok(src: &str, options: &RunOptions) -> (String, String) {
+    let (exit_code, stdout, stderr) = run_with(src, options);
+    assert_eq!(exit_code, ExitCode::SUCCESS, "{stderr}");
+    (stdout, stderr)
+}
+
+/// Runs the source with the options, and compares the exit code and the
+/// outputs, trimmed.
+macro_rules! run_test {
+    ($src:expr, $options:expr, exit_code = $exit_code:expr, stdout = $stdout:expr, stderr = $stderr:expr) => {
+        let (exit_code, stdout, stderr) = $crate::common::run_with($src, &$options);
+        assert_eq!(exit_code, std::process::ExitCode::from($exit_code));
+        assert_eq!(stdout.trim(), $stdout.trim());
+        assert_eq!(stderr.trim(), $stderr.trim());
+    };
+}
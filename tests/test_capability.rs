@@ -0,0 +1,59 @@
+#[macro_use]
+mod common;
+
+use codecrafters_interpreter::{Capabilities, Capability, RunOptions};
+
+fn with(capabilities: Capabilities) -> RunOptions {
+    RunOptions {
+        capabilities,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn default_capabilities_keep_clock() {
+    run_test!(
+        r#"print clock() > 0; print len("12");"#,
+        with(Capabilities::default()),
+        exit_code = 0,
+        stdout = "true\n2",
+        stderr = ""
+    );
+}
+
+#[test]
+fn denied_native_is_undefined() {
+    run_test!(
+        "print clock();",
+        with(Capabilities::none()),
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Undefined variable 'clock'. It requires the 'time' capability."
+    );
+
+    run_test!(
+        "\ngetenv(\"HOME\");",
+        with(Capabilities::default()),
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 2] Error: Undefined variable 'getenv'. It requires the 'env' capability."
+    );
+}
+
+#[test]
+fn user_definition_shadows_denied_native() {
+    run_test!(
+        "fun clock() { return 1; } print clock();",
+        with(Capabilities::none()),
+        exit_code = 0,
+        stdout = "1",
+        stderr = ""
+    );
+}
+
+#[test]
+fn parse_capability_names() {
+    assert_eq!("fs_read".parse::<Capability>(), Ok(Capability::FsRead));
+    assert!("network".parse::<Capability>().is_err());
+    assert!(Capabilities::all().contains(Capability::Process));
+}
@@ -0,0 +1,88 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn lox(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn read_from_stdin() {
+    let output = lox(&["run", "-"], "#!/usr/bin/env lox\nprint 1 + 2;");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+
+    let output = lox(&["tokenize", "-"], "(");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "LEFT_PAREN ( null\nEOF  null\n"
+    );
+
+    let output = lox(&["parse", "-"], "1 + 2");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "(+ 1.0 2.0)\n");
+
+    let output = lox(&["evaluate", "-"], "1 + 2");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+}
+
+#[test]
+fn missing_file() {
+    for command in ["tokenize", "parse", "evaluate", "run"] {
+        let output = lox(&[command, "no/such/file.lox"], "");
+        assert_eq!(output.status.code(), Some(66));
+        assert!(output.stdout.is_empty());
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.starts_with("Error: Could not read 'no/such/file.lox': "),
+            "{stderr}"
+        );
+    }
+}
+
+#[test]
+fn json_format() {
+    let output = lox(&["tokenize", "--format", "json", "-"], "@");
+    assert_eq!(output.status.code(), Some(65));
+    assert!(output.stderr.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"{"tokens":[{"type":"EOF","lexeme":"","literal":null,"line":1,"column":2}],"errors":[{"code":"unexpected-character","message":"Unexpected character: @","span":{"line":1,"column":1,"end_column":2}}]}
+"#
+    );
+
+    let output = lox(&["parse", "--format", "json", "-"], "print nil;");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"{"statements":[{"type":"Print","line":1,"expr":{"type":"Literal","value":null,"line":1,"column":7}}],"errors":[]}
+"#
+    );
+}
+
+#[test]
+fn debug_from_stdin() {
+    let output = lox(&["debug", "-"], "print 1;\ncontinue\n");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Error: 'debug' needs a script file, not stdin.\n"
+    );
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use std::{fs, process::ExitCode};
+
+use codecrafters_interpreter::{lox_compile, lox_is_compiled, lox_run_compiled, RunOptions};
+
+const SRC: &str = r#"
+var greeting = "hello";
+fun counter() {
+    var n = 0;
+    fun inc() {
+        n = n + 1;
+        return n;
+    }
+    return inc;
+}
+var c = counter();
+c();
+print c() + 0.5;
+for (var i = 0; i < 3; i = i + 1) {
+    if (i == 1) print greeting + " world"; else print -i;
+}
+print nil == false or !true;
+"#;
+
+fn compile(src: &str, path: &str, optimize: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (exit_code, _, error) =
+        common::capture(|_, err_buf| lox_compile(src, path, optimize, &mut out, err_buf));
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert!(error.is_empty());
+    out
+}
+
+fn run_compiled(compiled: &[u8]) -> (ExitCode, String, String) {
+    common::capture(|ok_buf, err_buf| {
+        lox_run_compiled(compiled, &RunOptions::default(), ok_buf, err_buf)
+    })
+}
+
+#[test]
+fn runs_the_same_as_source() {
+    let (expected, _) = common::run_ok(SRC, &RunOptions::default());
+
+    for optimize in [false, true] {
+        let compiled = compile(SRC, "", optimize);
+        assert!(lox_is_compiled(&compiled));
+        assert!(!lox_is_compiled(SRC.as_bytes()));
+        assert_eq!(
+            run_compiled(&compiled),
+            (ExitCode::SUCCESS, expected.clone(), String::new())
+        );
+    }
+}
+
+#[test]
+fn keeps_lines_of_runtime_errors() {
+    let compiled = compile("print 1;\n\nprint -\"a\";", "", false);
+    assert_eq!(
+        run_compiled(&compiled),
+        (
+            ExitCode::from(70),
+            "1\n".to_string(),
+            "[line 3] Error: Operand must be number\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn does_not_compile_invalid_source() {
+    let mut out = Vec::new();
+    let (exit_code, _, error) =
+        common::capture(|_, err_buf| lox_compile("print ;", "", false, &mut out, err_buf));
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert!(out.is_empty());
+    assert!(!error.is_empty());
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut compiled = compile(SRC, "", false);
+    compiled[4] = compiled[4].wrapping_add(1);
+    let (exit_code, output, error) = run_compiled(&compiled);
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert_eq!(output, "");
+    assert!(
+        error.starts_with("Error: Compiled by format version"),
+        "{error}"
+    );
+}
+
+#[test]
+fn rejects_corrupted_programs() {
+    let mut compiled = compile(SRC, "", false);
+    let last = compiled.len() - 1;
+    compiled[last] ^= 1;
+    assert_eq!(
+        run_compiled(&compiled),
+        (
+            ExitCode::from(65),
+            String::new(),
+            "Error: Checksum does not match. The compiled program is corrupted.\n".to_string()
+        )
+    );
+
+    let (exit_code, _, _) = run_compiled(&compiled[..10]);
+    assert_eq!(exit_code, ExitCode::from(65));
+}
+
+#[test]
+fn rejects_stale_programs() {
+    let path = std::env::temp_dir().join(format!("lox_stale_{}.lox", std::process::id()));
+    fs::write(&path, SRC).unwrap();
+    let path_str = path.to_str().unwrap();
+    let compiled = compile(SRC, path_str, false);
+
+    let (exit_code, _, _) = run_compiled(&compiled);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    fs::write(&path, format!("{SRC}print 1;")).unwrap();
+    let result = run_compiled(&compiled);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        result,
+        (
+            ExitCode::from(65),
+            String::new(),
+            format!("Error: '{path_str}' changed since it was compiled. Compile it again.\n")
+        )
+    );
+}
+
+/// Compiled `src`, with the binding of the variable used at `offset` of it
+/// replaced, and the checksum computed again so that only the binding is
+/// wrong.
+fn with_binding(src: &str, offset: usize, binding: &[u8]) -> Vec<u8> {
+    let mut compiled = compile(src, "", false);
+    let variable = [
+        &[7, 0][..],
+        &(offset as u64).to_le_bytes(),
+        &1u64.to_le_bytes(),
+    ]
+    .concat();
+    // After the line and the column of the token.
+    let at = compiled
+        .windows(variable.len())
+        .position(|window| window == variable)
+        .unwrap()
+        + variable.len()
+        + 16;
+    compiled[at..at + binding.len()].copy_from_slice(binding);
+    // FNV-1a, as the header has it.
+    let checksum = compiled[16..]
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        });
+    compiled[8..16].copy_from_slice(&checksum.to_le_bytes());
+    compiled
+}
+
+#[test]
+fn rejects_bindings_out_of_range() {
+    let global = |symbol: u64| [&[1][..], &symbol.to_le_bytes()].concat();
+    let local =
+        |depth: u64, slot: u64| [&[2][..], &depth.to_le_bytes(), &slot.to_le_bytes()].concat();
+    let global_src = "var a = 1; print a;";
+    let local_src = "{ var a = 1; print a; }";
+
+    let ok = (ExitCode::SUCCESS, "1\n".to_string(), String::new());
+    assert_eq!(run_compiled(&with_binding(global_src, 17, &global(0))), ok);
+    assert_eq!(run_compiled(&with_binding(local_src, 19, &local(0, 0))), ok);
+
+    let malformed = (
+        ExitCode::from(65),
+        String::new(),
+        "Error: Malformed compiled program.\n".to_string(),
+    );
+    for compiled in [
+        with_binding(global_src, 17, &global(1)),
+        with_binding(global_src, 17, &global(u64::MAX)),
+        with_binding(local_src, 19, &local(1, 0)),
+        with_binding(local_src, 19, &local(0, 1)),
+        with_binding(local_src, 19, &local(0, 1 << 40)),
+    ] {
+        assert_eq!(run_compiled(&compiled), malformed);
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_coverage, RunOptions};
+
+/// Runs the source, and returns the exit code and the LCOV record.
+fn coverage(src: &str) -> (ExitCode, String) {
+    let mut lcov = Vec::new();
+    let (exit_code, _, _) = common::capture(|ok_buf, err_buf| {
+        lox_coverage(
+            src,
+            "rules.lox",
+            &RunOptions::default(),
+            ok_buf,
+            err_buf,
+            &mut lcov,
+        )
+    });
+    (exit_code, String::from_utf8(lcov).unwrap())
+}
+
+#[test]
+fn lines_and_branches() {
+    let src = "fun grade(score) {
+    if (score >= 90) return \"A\";
+    if (score >= 50) {
+        return \"B\";
+    } else {
+        return \"C\";
+    }
+}
+print grade(95);
+print grade(60);
+fun unused() {
+    print \"never\";
+}";
+    let (exit_code, lcov) = coverage(src);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(
+        lcov,
+        "TN:
+SF:rules.lox
+BRDA:2,0,0,1
+BRDA:2,0,1,1
+BRDA:3,0,0,1
+BRDA:3,0,1,0
+BRF:4
+BRH:3
+DA:1,1
+DA:2,3
+DA:3,2
+DA:4,1
+DA:5,0
+DA:6,0
+DA:9,1
+DA:10,1
+DA:11,1
+DA:12,0
+LF:10
+LH:7
+end_of_record
+"
+    );
+}
+
+#[test]
+fn branches_on_the_same_line() {
+    let src = "var a = 1;\nif (a > 0) print 1; if (a < 0) print 2; else print 3;\nfun f() {\n    if (true) {}\n}";
+    let (_, lcov) = coverage(src);
+    let branches: Vec<_> = lcov.lines().filter(|line| line.starts_with("BR")).collect();
+    assert_eq!(
+        branches,
+        [
+            "BRDA:2,0,0,1",
+            "BRDA:2,0,1,0",
+            "BRDA:2,1,0,0",
+            "BRDA:2,1,1,1",
+            // Never ran, as `f` is not called.
+            "BRDA:4,0,0,-",
+            "BRDA:4,0,1,-",
+            "BRF:6",
+            "BRH:2",
+        ]
+    );
+}
+
+#[test]
+fn loops_count_every_run() {
+    let src = "for (var i = 0; i < 3; i = i + 1) {\n    print i;\n}";
+    let (_, lcov) = coverage(src);
+    // The loop, its initializer and its block each count on the first line.
+    assert!(lcov.contains("DA:1,5\nDA:2,3\n"));
+}
+
+#[test]
+fn written_after_an_error() {
+    let (exit_code, lcov) = coverage("print 1;\nprint -nil;\nprint 3;");
+    assert_eq!(exit_code, ExitCode::from(70));
+    assert!(lcov.contains("DA:1,1\nDA:2,1\nDA:3,0\nLF:3\nLH:2\n"));
+}
@@ -0,0 +1,218 @@
+use std::{fs, io::Cursor, process::ExitCode};
+
+use codecrafters_interpreter::lox_dap;
+
+const PROGRAM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/test_suites/fib_debug.lox"
+);
+
+/// Serves the requests, and returns the exit code and the bodies of the
+/// messages sent back.
+fn serve(requests: &[(&str, &str)]) -> (ExitCode, Vec<String>) {
+    let input: String = requests
+        .iter()
+        .enumerate()
+        .map(|(i, (command, arguments))| {
+            let body = format!(
+                r#"{{"seq":{},"type":"request","command":"{command}","arguments":{arguments}}}"#,
+                i + 1
+            );
+            format!("Content-Length: {}\r\n\r\n{body}", body.len())
+        })
+        .collect();
+    let mut output = Vec::new();
+    let exit_code = lox_dap(Cursor::new(input), &mut output);
+
+    let mut output = String::from_utf8(output).unwrap();
+    let mut bodies = Vec::new();
+    while let Some(rest) = output.strip_prefix("Content-Length: ") {
+        let (length, rest) = rest.split_once("\r\n\r\n").unwrap();
+        let length: usize = length.parse().unwrap();
+        bodies.push(rest[..length].to_string());
+        output = rest[length..].to_string();
+    }
+    assert!(output.is_empty());
+    (exit_code, bodies)
+}
+
+/// Requests starting the program, which stops on entry if asked.
+fn launch(program: &str, stop_on_entry: bool, breakpoints: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("initialize", r#"{"adapterID":"lox"}"#.to_string()),
+        (
+            "launch",
+            format!(r#"{{"program":"{program}","stopOnEntry":{stop_on_entry}}}"#),
+        ),
+        (
+            "setBreakpoints",
+            format!(r#"{{"source":{{"path":"{program}"}},"breakpoints":{breakpoints}}}"#),
+        ),
+        ("configurationDone", "{}".to_string()),
+    ]
+}
+
+/// Bodies of the messages which are the events of the kind.
+fn events<'a>(bodies: &'a [String], event: &str) -> Vec<&'a str> {
+    let pattern = format!(r#""type":"event","event":"{event}""#);
+    bodies
+        .iter()
+        .filter(|body| body.contains(&pattern))
+        .map(String::as_str)
+        .collect()
+}
+
+fn run(requests: Vec<(&str, String)>) -> (ExitCode, Vec<String>) {
+    let requests: Vec<_> = requests
+        .iter()
+        .map(|(command, arguments)| (*command, arguments.as_str()))
+        .collect();
+    serve(&requests)
+}
+
+#[test]
+fn breakpoint_and_inspection() {
+    let mut requests = launch(PROGRAM, false, r#"[{"line":3}]"#);
+    requests.extend([
+        ("stackTrace", r#"{"threadId":1}"#.to_string()),
+        ("scopes", r#"{"frameId":1}"#.to_string()),
+        ("variables", r#"{"variablesReference":3}"#.to_string()),
+        ("variables", r#"{"variablesReference":1}"#.to_string()),
+        (
+            "setBreakpoints",
+            r#"{"source":{},"breakpoints":[]}"#.to_string(),
+        ),
+        ("continue", r#"{"threadId":1}"#.to_string()),
+        ("disconnect", "{}".to_string()),
+    ]);
+    let (exit_code, bodies) = run(requests);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    assert_eq!(
+        bodies[0],
+        r#"{"seq":1,"type":"response","request_seq":1,"success":true,"command":"initialize","body":{"supportsConfigurationDoneRequest":true}}"#
+    );
+    assert!(bodies[1].contains(r#""event":"initialized""#));
+    assert!(bodies[3].contains(r#""breakpoints":[{"verified":true,"line":3}]"#));
+    assert!(bodies[5].contains(
+        r#""event":"stopped","body":{"reason":"breakpoint","threadId":1,"allThreadsStopped":true}"#
+    ));
+
+    let source = format!(r#""source":{{"path":"{PROGRAM}"}}"#);
+    assert!(bodies[6].contains(&format!(
+        r#""body":{{"totalFrames":2,"stackFrames":[{{"id":1,"name":"fib",{source},"line":3,"column":1}},{{"id":0,"name":"<script>",{source},"line":7,"column":1}}]}}"#
+    )));
+    assert!(bodies[7].contains(
+        r#""scopes":[{"name":"Locals","variablesReference":3,"expensive":false},{"name":"Globals","variablesReference":1,"expensive":false}]"#
+    ));
+    assert!(bodies[8].contains(r#""variables":[{"name":"n","value":"3","variablesReference":0}]"#));
+    assert!(bodies[9].contains(r#"{"name":"fib","value":"<fn fib>","variablesReference":0},{"name":"x","value":"10","variablesReference":0}"#));
+
+    // Without the breakpoint, the program runs to the end.
+    let outputs = events(&bodies, "output");
+    assert_eq!(outputs.len(), 2);
+    assert!(outputs[0].contains(r#""body":{"category":"stdout","output":"2\n"}"#));
+    assert!(events(&bodies, "exited")[0].contains(r#""body":{"exitCode":0}"#));
+    assert_eq!(events(&bodies, "terminated").len(), 1);
+    assert!(bodies.last().unwrap().contains(r#""command":"disconnect""#));
+}
+
+#[test]
+fn stepping() {
+    let mut requests = launch(PROGRAM, true, "[]");
+    for command in ["next", "next", "stepIn", "next", "stepOut"] {
+        requests.push((command, r#"{"threadId":1}"#.to_string()));
+        requests.push(("stackTrace", r#"{"threadId":1}"#.to_string()));
+    }
+    requests.push(("disconnect", "{}".to_string()));
+    let (exit_code, bodies) = run(requests);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    let stopped = events(&bodies, "stopped");
+    assert!(stopped[0].contains(r#""reason":"entry""#));
+    assert!(stopped[1..]
+        .iter()
+        .all(|body| body.contains(r#""reason":"step""#)));
+
+    // The innermost frame of each stack trace.
+    let top: Vec<_> = bodies
+        .iter()
+        .filter(|body| body.contains(r#""command":"stackTrace""#))
+        .map(|body| {
+            let frame = body.split(r#""stackFrames":[{"#).nth(1).unwrap();
+            let name = frame.split(r#""name":""#).nth(1).unwrap();
+            let line = frame.split(r#""line":"#).nth(1).unwrap();
+            (
+                name.split('"').next().unwrap().to_string(),
+                line.split(',').next().unwrap().parse::<usize>().unwrap(),
+            )
+        })
+        .collect();
+    let expected = [
+        ("<script>", 6),
+        ("<script>", 7),
+        ("fib", 2),
+        ("fib", 3),
+        ("<script>", 8),
+    ];
+    assert_eq!(top, expected.map(|(name, line)| (name.to_string(), line)));
+
+    // Disconnecting stops the program, which sends no more events.
+    assert!(events(&bodies, "exited").is_empty());
+    assert_eq!(events(&bodies, "output").len(), 1);
+}
+
+#[test]
+fn breakpoint_in_single_line_loop() {
+    // The breakpoint stops once on each iteration.
+    let program = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/test_suites/loop_debug.lox"
+    );
+    let mut requests = launch(program, false, r#"[{"line":2}]"#);
+    for _ in 0..3 {
+        requests.push(("continue", r#"{"threadId":1}"#.to_string()));
+    }
+    requests.push(("disconnect", "{}".to_string()));
+    let (exit_code, bodies) = run(requests);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(events(&bodies, "stopped").len(), 3);
+    assert!(events(&bodies, "output")[0].contains(r#""output":"3\n""#));
+}
+
+#[test]
+fn errors_of_the_program() {
+    let path = std::env::temp_dir().join("lox_dap_errors.lox");
+    fs::write(&path, "print 1;\nprint -\"a\";\n").unwrap();
+    let mut requests = launch(path.to_str().unwrap(), false, "[]");
+    requests.push(("disconnect", "{}".to_string()));
+    let (_, bodies) = run(requests);
+    fs::remove_file(path).unwrap();
+
+    let outputs = events(&bodies, "output");
+    assert!(outputs[0].contains(r#""category":"stdout","output":"1\n""#));
+    assert!(outputs[1]
+        .contains(r#""category":"stderr","output":"[line 2] Error: Operand must be number\n""#));
+    assert!(events(&bodies, "exited")[0].contains(r#""exitCode":70"#));
+}
+
+#[test]
+fn invalid_requests() {
+    let (exit_code, bodies) = serve(&[
+        ("launch", r#"{"program":"/no/such/file.lox"}"#),
+        ("stackTrace", r#"{"threadId":1}"#),
+        ("evaluate", r#"{"expression":"1"}"#),
+    ]);
+    // The input ended without a 'disconnect'.
+    assert_eq!(exit_code, ExitCode::FAILURE);
+
+    assert!(bodies[0].contains(
+        r#""success":false,"command":"launch","message":"Could not read '/no/such/file.lox': "#
+    ));
+    assert!(bodies[1].contains(
+        r#""success":false,"command":"stackTrace","message":"The program is not stopped.""#
+    ));
+    assert!(bodies[2].contains(
+        r#""success":false,"command":"evaluate","message":"Unknown command 'evaluate'.""#
+    ));
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::{io::Cursor, process::ExitCode};
+
+use codecrafters_interpreter::{lox_debug, RunOptions};
+
+const SRC: &str = "fun fib(n) {
+    if (n < 2) return n;
+    var a = fib(n - 1);
+    return a + fib(n - 2);
+}
+var x = 10;
+print fib(3);
+print \"done\";";
+
+/// Debugs the source with the commands, and returns the exit code and what
+/// is written to stdout.
+fn debug(src: &str, commands: &str) -> (ExitCode, String) {
+    let input = Cursor::new(commands.to_string());
+    let (exit_code, output, error) = common::capture(|ok_buf, err_buf| {
+        lox_debug(src, &RunOptions::default(), input, ok_buf, err_buf)
+    });
+    assert!(error.is_empty());
+    (exit_code, output)
+}
+
+#[test]
+fn stops_at_first_line() {
+    let (exit_code, output) = debug(SRC, "continue\n");
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(output, "[line 1] fun fib(n) {\n(lox) 2\ndone\n");
+}
+
+#[test]
+fn breakpoint_and_inspection() {
+    let (exit_code, output) = debug(
+        SRC,
+        "break 3\ncontinue\nlocals\nbacktrace\nprint n * 2\nprint fib(5) + x\nquit\n",
+    );
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(
+        output,
+        "[line 1] fun fib(n) {
+(lox) Breakpoint at line 3.
+(lox) [line 3] var a = fib(n - 1);
+(lox) n = 3
+(lox) #0 fib at line 3
+#1 <script> at line 7
+(lox) 6
+(lox) 15
+(lox) "
+    );
+}
+
+#[test]
+fn next_and_step() {
+    // `step` enters the call on the line, `next` runs over it.
+    let (_, output) = debug(
+        SRC,
+        "break 7\ncontinue\nstep\nnext\nnext\nstep\nbacktrace\nquit\n",
+    );
+    assert!(output.ends_with(
+        "(lox) [line 7] print fib(3);
+(lox) [line 2] if (n < 2) return n;
+(lox) [line 3] var a = fib(n - 1);
+(lox) [line 4] return a + fib(n - 2);
+(lox) [line 2] if (n < 2) return n;
+(lox) #0 fib at line 2
+#1 fib at line 4
+#2 <script> at line 7
+(lox) "
+    ));
+}
+
+#[test]
+fn steps_through_single_line_loops() {
+    // Each iteration stops once, the first with the line of the loop.
+    let (_, output) = debug(
+        "var i = 0;\nwhile (i < 3) if (i < 5) i = i + 1;\nfor (var j = 0; j < 2; j = j + 1) print j;",
+        "step\nstep\nstep\nstep\nstep\nstep\n",
+    );
+    assert_eq!(
+        output,
+        "[line 1] var i = 0;
+(lox) [line 2] while (i < 3) if (i < 5) i = i + 1;
+(lox) [line 2] while (i < 3) if (i < 5) i = i + 1;
+(lox) [line 2] while (i < 3) if (i < 5) i = i + 1;
+(lox) [line 3] for (var j = 0; j < 2; j = j + 1) print j;
+(lox) 0
+[line 3] for (var j = 0; j < 2; j = j + 1) print j;
+(lox) 1
+"
+    );
+}
+
+#[test]
+fn end_of_input_runs_to_the_end() {
+    let (exit_code, output) = debug(SRC, "break 4\n");
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert!(output.ends_with("(lox) \n2\ndone\n"));
+}
+
+#[test]
+fn invalid_commands() {
+    let (_, output) = debug(
+        "var a = 1;",
+        "foo\nbreak x\nprint nope\nprint 1 2\nlocals\nstep\n",
+    );
+    assert_eq!(
+        output,
+        "[line 1] var a = 1;
+(lox) Unknown command 'foo'. Type 'help' for the commands.
+(lox) Usage: break <line>
+(lox) Error: Undefined variable 'nope'.
+(lox) Error at '2': Expected end of expression.
+(lox) args = []
+(lox) "
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_fmt, RunOptions};
+use common::run_ok;
+
+fn fmt(src: &str, check: bool) -> (ExitCode, String, String) {
+    common::capture(|ok_buf, err_buf| lox_fmt(src, check, ok_buf, err_buf))
+}
+
+macro_rules! fmt_test {
+    ($src:expr, $expected:expr) => {
+        let (exit_code, formatted, err) = fmt($src, false);
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(formatted, $expected);
+        assert!(err.is_empty());
+
+        // Formatting again must not change anything.
+        assert_eq!(fmt(&formatted, true).0, ExitCode::SUCCESS);
+    };
+}
+
+#[test]
+fn spacing_and_braces() {
+    fmt_test!(
+        "var   x=1;fun add(a,b){return a+b;}\nif(x==1){print add(x,-2);}else print !(x>2);",
+        "var x = 1;
+fun add(a, b) {
+    return a + b;
+}
+if (x == 1) {
+    print add(x, -2);
+} else print !(x > 2);
+"
+    );
+}
+
+#[test]
+fn loops_and_empty_blocks() {
+    fmt_test!(
+        "for(var i=0;i<3;i=i+1)print i;\nfor(;;){ }\nwhile(true)\n{\n}",
+        "for (var i = 0; i < 3; i = i + 1) print i;
+for (;;) {}
+while (true) {}
+"
+    );
+}
+
+#[test]
+fn keeps_comments() {
+    fmt_test!(
+        "// header\nvar x = 1;   // trailing\nfun f() {\n// inside\nreturn x;\n  // last\n}\n// eof",
+        "// header
+var x = 1;  // trailing
+fun f() {
+    // inside
+    return x;
+    // last
+}
+// eof
+"
+    );
+}
+
+#[test]
+fn keeps_comments_at_their_tokens() {
+    fmt_test!(
+        "if (a) {\nprint 1;\n} // after if\nelse {\nprint 2;\n}",
+        "if (a) {\n    print 1;\n}  // after if\nelse {\n    print 2;\n}\n"
+    );
+    fmt_test!(
+        "print f(1, // one\n2);\nprint g(\n// first\n1);",
+        "print f(1,  // one\n    2);\nprint g(\n    // first\n    1);\n"
+    );
+    fmt_test!(
+        "if (a) // header\nprint 1;\nwhile (b) { // open\n}",
+        "if (a)  // header\n    print 1;\nwhile (b) {  // open\n}\n"
+    );
+}
+
+#[test]
+fn collapses_blank_lines() {
+    fmt_test!(
+        "\n\nprint 1;\n\n\n\nprint 2;\nprint 3;\n\n",
+        "print 1;
+
+print 2;
+print 3;
+"
+    );
+}
+
+#[test]
+fn keeps_shebang() {
+    fmt_test!(
+        "#!/usr/bin/env lox\nprint  1;",
+        "#!/usr/bin/env lox\nprint 1;\n"
+    );
+}
+
+#[test]
+fn field_access() {
+    fmt_test!(
+        "print  a.b;  // field\nfun f(x) { return x.y ; }\nvar c = f(a).d;",
+        "print a.b;  // field\nfun f(x) {\n    return x.y;\n}\nvar c = f(a).d;\n"
+    );
+}
+
+#[test]
+fn formatted_program_runs_the_same() {
+    let options = RunOptions::default();
+    for src in [
+        include_str!("./test_suites/recursive.lox"),
+        include_str!("./test_suites/nested_func.lox"),
+        include_str!("./test_suites/block_scope_resolution.lox"),
+    ] {
+        let (exit_code, formatted, _) = fmt(src, false);
+        assert_eq!(exit_code, ExitCode::SUCCESS);
+        assert_eq!(fmt(&formatted, false).1, formatted);
+        assert_eq!(run_ok(&formatted, &options), run_ok(src, &options));
+    }
+}
+
+#[test]
+fn check() {
+    assert_eq!(
+        fmt("print 1;\n", true),
+        (ExitCode::SUCCESS, "".into(), "".into())
+    );
+    assert_eq!(
+        fmt("print 1 ;", true),
+        (
+            ExitCode::FAILURE,
+            "".into(),
+            "Error: Source is not formatted.\n".into()
+        )
+    );
+    assert_eq!(fmt("print 1;\r\n// c\r\n", true).0, ExitCode::SUCCESS);
+}
+
+#[test]
+fn parse_error() {
+    let (exit_code, formatted, err) = fmt("print (1;", false);
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert!(formatted.is_empty());
+    assert!(!err.is_empty());
+}
@@ -0,0 +1,85 @@
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_parse_json, lox_tokenize_json};
+
+fn tokenize(src: &str) -> (ExitCode, String) {
+    let mut ok_buf = Vec::new();
+    let exit_code = lox_tokenize_json(src, &mut ok_buf);
+    (exit_code, String::from_utf8(ok_buf).unwrap())
+}
+
+fn parse(src: &str) -> (ExitCode, String) {
+    let mut ok_buf = Vec::new();
+    let exit_code = lox_parse_json(src, &mut ok_buf);
+    (exit_code, String::from_utf8(ok_buf).unwrap())
+}
+
+#[test]
+fn tokens() {
+    let (exit_code, output) = tokenize("var s = \"hi\";\nprint 1.50;");
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(
+        output,
+        r#"{"tokens":[{"type":"VAR","lexeme":"var","literal":null,"line":1,"column":1},{"type":"IDENTIFIER","lexeme":"s","literal":null,"line":1,"column":5},{"type":"EQUAL","lexeme":"=","literal":null,"line":1,"column":7},{"type":"STRING","lexeme":"\"hi\"","literal":"hi","line":1,"column":9},{"type":"SEMICOLON","lexeme":";","literal":null,"line":1,"column":13},{"type":"PRINT","lexeme":"print","literal":null,"line":2,"column":1},{"type":"NUMBER","lexeme":"1.50","literal":1.5,"line":2,"column":7},{"type":"SEMICOLON","lexeme":";","literal":null,"line":2,"column":11},{"type":"EOF","lexeme":"","literal":null,"line":2,"column":12}],"errors":[]}
+"#
+    );
+}
+
+#[test]
+fn tokenize_errors() {
+    let (exit_code, output) = tokenize("@\n\"open");
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert_eq!(
+        output,
+        r#"{"tokens":[{"type":"EOF","lexeme":"","literal":null,"line":2,"column":6}],"errors":[{"code":"unexpected-character","message":"Unexpected character: @","span":{"line":1,"column":1,"end_column":2}},{"code":"unterminated-string","message":"Unterminated string.","span":{"line":2,"column":1,"end_column":6}}]}
+"#
+    );
+}
+
+#[test]
+fn statements() {
+    let src = "fun f(a, b) {\n    if (a) return -b;\n}\nvar x;\nx = f(true, (2));";
+    let (exit_code, output) = parse(src);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(
+        output,
+        r#"{"statements":[{"type":"FunctionDef","line":1,"name":"f","parameters":["a","b"],"body":[{"type":"If","line":2,"condition":{"type":"Variable","name":"a","line":2,"column":9},"body":{"type":"Return","line":2,"value":{"type":"Unary","operator":"-","right":{"type":"Variable","name":"b","line":2,"column":20}}},"else_body":null}]},{"type":"VarDecl","line":4,"name":"x","value":null},{"type":"Expression","line":5,"expr":{"type":"Assign","assignee":{"type":"Variable","name":"x","line":5,"column":1},"value":{"type":"FunctionCall","callee":{"type":"Variable","name":"f","line":5,"column":5},"arguments":[{"type":"Literal","value":true,"line":5,"column":7},{"type":"Grouping","inner":{"type":"Literal","value":2,"line":5,"column":14}}]}}}],"errors":[]}
+"#
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let (exit_code, output) = parse("print 1;\nprint (2;");
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert_eq!(
+        output,
+        r#"{"statements":null,"errors":[{"code":"expected-closing-parenthesis","message":"Expected ')'.","span":{"line":2,"column":9,"end_column":10}}]}
+"#
+    );
+
+    let (_, output) = parse("var a = \"x: y\";\nprint a +;");
+    assert_eq!(
+        output,
+        r#"{"statements":null,"errors":[{"code":"expected-expression","message":"Expected expression.","span":{"line":2,"column":10,"end_column":11}}]}
+"#
+    );
+
+    // Tokenizing fails first, so nothing is parsed.
+    let (exit_code, output) = parse("print #;");
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert!(output.starts_with(r#"{"statements":null,"errors":[{"code":"unexpected-character""#));
+}
+
+#[test]
+fn error_spanning_lines() {
+    // The error is at the part of the string on the line where it ends.
+    let (_, output) = tokenize("print \"a\nbc");
+    assert!(
+        output.ends_with(
+            r#""message":"Unterminated string.","span":{"line":2,"column":1,"end_column":3}}]}
+"#
+        ),
+        "{output}"
+    );
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use codecrafters_interpreter::RunOptions;
+use common::run_ok;
+
+/// Each iteration makes a block whose function refers to itself, which is a
+/// cycle of the block and the function.
+const CLOSURES: &str = r#"
+    for (var i = 0; i < 20000; i = i + 1) {
+        fun again() {
+            return again;
+        }
+    }
+    var stats = gc_stats();
+    print get(stats, "live") < 2000;
+    print get(stats, "collections") > 0;
+    print get(stats, "freed") > 15000;
+"#;
+
+#[test]
+fn closures_in_loop_stay_bounded() {
+    let (output, _) = run_ok(CLOSURES, &RunOptions::default());
+    assert_eq!(output, "true\ntrue\ntrue\n");
+}
+
+#[test]
+fn cyclic_lists_are_freed() {
+    let src = r#"
+        for (var i = 0; i < 5000; i = i + 1) {
+            var l = list();
+            push(l, l);
+        }
+        print get(gc_stats(), "freed") > 4000;
+    "#;
+    let (output, _) = run_ok(src, &RunOptions::default());
+    assert_eq!(output, "true\n");
+}
+
+#[test]
+fn reachable_closures_are_kept() {
+    let src = r#"
+        var counters = list();
+        fun counter() {
+            var count = 0;
+            fun increment() {
+                count = count + 1;
+                return count;
+            }
+            return increment;
+        }
+        for (var i = 0; i < 3000; i = i + 1) push(counters, counter());
+
+        var first = get(counters, 0);
+        first();
+        print first();
+        print get(counters, 2999)();
+        print get(gc_stats(), "collections") > 0;
+    "#;
+    let (output, _) = run_ok(src, &RunOptions::default());
+    assert_eq!(output, "2\n1\ntrue\n");
+}
+
+#[test]
+fn gc_log() {
+    let options = RunOptions {
+        gc_log: true,
+        ..Default::default()
+    };
+    let (_, log) = run_ok(CLOSURES, &options);
+    assert!(log.starts_with("[gc] collection 1: freed "), "{log}");
+
+    // The last collection is made at the end of the run, and frees the
+    // globals, which hold `f` closing over them, and the `args` list.
+    let (_, log) = run_ok("fun f() { return f; }", &options);
+    assert_eq!(log, "[gc] collection 1: freed 2 of 2 objects\n");
+
+    let (_, log) = run_ok(CLOSURES, &RunOptions::default());
+    assert!(log.is_empty());
+}
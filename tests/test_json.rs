@@ -0,0 +1,157 @@
+#[macro_use]
+mod common;
+
+use codecrafters_interpreter::RunOptions;
+
+/// Lox strings cannot escape '"', so JSON documents are given as `args`.
+macro_rules! json_test {
+    ($src:expr, args = $args:expr, exit_code = $exit_code:expr, stdout = $stdout:expr, stderr = $stderr:expr) => {
+        let options = RunOptions {
+            args: $args.iter().map(|arg: &&str| arg.to_string()).collect(),
+            ..Default::default()
+        };
+        run_test!(
+            $src,
+            options,
+            exit_code = $exit_code,
+            stdout = $stdout,
+            stderr = $stderr
+        );
+    };
+    ($src:expr, args = $args:expr, stdout = $stdout:expr) => {
+        json_test!(
+            $src,
+            args = $args,
+            exit_code = 0,
+            stdout = $stdout,
+            stderr = ""
+        );
+    };
+}
+
+#[test]
+fn parse_values() {
+    json_test!(
+        r#"
+        var config = json_parse(get(args, 0));
+        print get(config, "name");
+        print get(config, "tags");
+        print get(get(config, "tags"), 1);
+        print get(config, "nested");
+        print get(config, "missing");
+        print keys(config);
+        "#,
+        args = [r#"{"name": "lox", "tags": [1, 2.5, true, null], "nested": {}}"#],
+        stdout = "lox\n[1, 2.5, true, nil]\n2.5\n{}\nnil\n[name, tags, nested]"
+    );
+}
+
+#[test]
+fn stringify_values() {
+    json_test!(
+        r#"
+        var report = map();
+        set(report, "total", 3);
+        set(report, "ratio", 0.5);
+        var items = list();
+        push(items, "C:\lox");
+        push(items, nil);
+        push(items, false);
+        set(report, "items", items);
+        set(report, "empty", list());
+        print json_stringify(report);
+        print json_stringify(report, 2);
+        "#,
+        args = [],
+        stdout = r#"
+{"total":3,"ratio":0.5,"items":["C:\\lox",null,false],"empty":[]}
+{
+  "total": 3,
+  "ratio": 0.5,
+  "items": [
+    "C:\\lox",
+    null,
+    false
+  ],
+  "empty": []
+}"#
+    );
+}
+
+#[test]
+fn round_trip() {
+    json_test!(
+        "print json_stringify(json_parse(get(args, 0)));",
+        args = [r#" {"a":[1,{"b":"\"\u00e9\ud83d\ude00"}],"c":-1.5e3} "#],
+        stdout = r#"{"a":[1,{"b":"\"é😀"}],"c":-1500}"#
+    );
+}
+
+#[test]
+fn repeated_keys() {
+    json_test!(
+        r#"
+        var config = json_parse(get(args, 0));
+        print config;
+        print get(config, "a");
+        "#,
+        args = [r#"{"a": [1], "b": 2, "a": 3}"#],
+        stdout = "{a: 3, b: 2}\n3"
+    );
+}
+
+#[test]
+fn malformed_json() {
+    let src = "json_parse(get(args, 0));";
+
+    json_test!(
+        src,
+        args = ["[1, 2"],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Invalid JSON: Expected ',' or ']' at byte 5."
+    );
+
+    json_test!(
+        src,
+        args = [r#"{"a" 1}"#],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Invalid JSON: Expected ':' at byte 5."
+    );
+
+    json_test!(
+        src,
+        args = ["01"],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Invalid JSON: Unexpected trailing characters at byte 1."
+    );
+
+    json_test!(
+        src,
+        args = [r#"["\ud83d"]"#],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Invalid JSON: Unpaired surrogate at byte 8."
+    );
+}
+
+#[test]
+fn not_serializable() {
+    json_test!(
+        "fun f() {}\nvar l = list();\npush(l, f);\njson_stringify(l);",
+        args = [],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 4] Error: Cannot convert <fn f> to JSON."
+    );
+
+    json_test!(
+        "var l = list();\npush(l, l);\njson_stringify(l);",
+        args = [],
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 3] Error: Cannot convert cyclic list to JSON."
+    );
+}
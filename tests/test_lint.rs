@@ -0,0 +1,156 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::lox_lint;
+
+macro_rules! lint_test {
+    ($src:expr, $expected:expr) => {
+        let (exit_code, ok_buf, err_buf) =
+            common::capture(|ok_buf, err_buf| lox_lint($src, ok_buf, err_buf));
+
+        let expected: &str = $expected;
+        let expected_code = if expected.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+        assert_eq!(exit_code, expected_code);
+        assert_eq!(ok_buf.trim(), expected.trim());
+        assert!(err_buf.is_empty());
+    };
+}
+
+#[test]
+fn clean_program() {
+    lint_test!(include_str!("./test_suites/recursive.lox"), "");
+}
+
+#[test]
+fn unused_locals_and_parameters() {
+    lint_test!(
+        r#"
+        var global = 1;
+        fun f(used, unused, _ignored) {
+            var local = used;
+            var _scratch = 2;
+        }
+        f(1, 2, 3);
+        "#,
+        "[line 3] Warning: Unused parameter 'unused'. [unused-parameter]
+[line 4] Warning: Unused variable 'local'. [unused-variable]"
+    );
+}
+
+#[test]
+fn shadowing() {
+    lint_test!(
+        r#"
+        var x = 1;
+        {
+            var x = 2;
+            print x;
+        }
+        fun f(x) { return x; }
+        print f(1);
+        "#,
+        "[line 4] Warning: Variable 'x' shadows the one declared at line 2. [shadowing]
+[line 7] Warning: Variable 'x' shadows the one declared at line 2. [shadowing]"
+    );
+}
+
+#[test]
+fn unreachable_code() {
+    lint_test!(
+        r#"
+        fun f() {
+            return 1;
+            print "never";
+            print "never either";
+        }
+        print f();
+        "#,
+        "[line 4] Warning: Unreachable code after return. [unreachable-code]"
+    );
+}
+
+#[test]
+fn constant_condition() {
+    lint_test!(
+        r#"
+        if (true) print 1;
+        while ((nil)) print 2;
+        if (1 < 2) print 3;
+        "#,
+        "[line 2] Warning: Condition is always true. [constant-condition]
+[line 3] Warning: Condition is always false. [constant-condition]"
+    );
+}
+
+#[test]
+fn wrong_argument_count() {
+    lint_test!(
+        r#"
+        fun caller() { return callee(1, 2); }
+        fun callee(a) { return a; }
+        print caller();
+        print callee();
+        var callee2 = callee;
+        callee = clock;
+        print callee();
+        "#,
+        "[line 2] Warning: Function 'callee' takes 1 arguments, but 2 are given. [wrong-argument-count]
+[line 5] Warning: Function 'callee' takes 1 arguments, but 0 are given. [wrong-argument-count]"
+    );
+}
+
+#[test]
+fn self_assignment() {
+    lint_test!(
+        "var a = 1;\na = a;\na = (a);",
+        "[line 2] Warning: Variable 'a' is assigned to itself. [self-assignment]"
+    );
+}
+
+#[test]
+fn suppressed_by_comment() {
+    lint_test!(
+        r#"
+        fun f() {
+            var a = 1; // lint: allow(unused-variable)
+            // lint: allow(unused-variable, shadowing)
+            var f = 2;
+            var b = 3;
+        }
+        f();
+        "#,
+        "[line 6] Warning: Unused variable 'b'. [unused-variable]"
+    );
+}
+
+#[test]
+fn multi_line_signature() {
+    lint_test!(
+        r#"
+        fun f(
+            a,
+            // lint: allow(unused-parameter)
+            b,
+            c
+        ) {
+            print a;
+        }
+        f(1, 2, 3);
+        "#,
+        "[line 6] Warning: Unused parameter 'c'. [unused-parameter]"
+    );
+}
+
+#[test]
+fn parse_error() {
+    let (mut ok_buf, mut err_buf) = (Vec::new(), Vec::new());
+    let exit_code = lox_lint("var = 1;", &mut ok_buf, &mut err_buf);
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert!(ok_buf.is_empty());
+    assert!(!err_buf.is_empty());
+}
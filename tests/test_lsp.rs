@@ -0,0 +1,198 @@
+use std::{io::Cursor, process::ExitCode};
+
+use codecrafters_interpreter::lox_lsp;
+
+const URI: &str = "file:///test.lox";
+
+/// Runs the server on the messages, and returns the exit code and the
+/// bodies of the messages sent back.
+fn serve(messages: &[String]) -> (ExitCode, Vec<String>) {
+    let input: String = messages
+        .iter()
+        .map(|body| format!("Content-Length: {}\r\n\r\n{body}", body.len()))
+        .collect();
+    let mut output = Vec::new();
+    let exit_code = lox_lsp(Cursor::new(input), &mut output);
+
+    let mut output = String::from_utf8(output).unwrap();
+    let mut bodies = Vec::new();
+    while let Some(rest) = output.strip_prefix("Content-Length: ") {
+        let (length, rest) = rest.split_once("\r\n\r\n").unwrap();
+        let length: usize = length.parse().unwrap();
+        bodies.push(rest[..length].to_string());
+        output = rest[length..].to_string();
+    }
+    assert!(output.is_empty());
+    (exit_code, bodies)
+}
+
+fn request(id: usize, method: &str, params: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","id":{id},"method":"{method}","params":{params}}}"#)
+}
+
+fn notification(method: &str, params: &str) -> String {
+    format!(r#"{{"jsonrpc":"2.0","method":"{method}","params":{params}}}"#)
+}
+
+fn did_open(text: &str) -> String {
+    let text = text.replace('\n', "\\n").replace('"', "\\\"");
+    notification(
+        "textDocument/didOpen",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{URI}","languageId":"lox","version":1,"text":"{text}"}}}}"#
+        ),
+    )
+}
+
+fn at(line: usize, character: usize) -> String {
+    format!(
+        r#"{{"textDocument":{{"uri":"{URI}"}},"position":{{"line":{line},"character":{character}}}}}"#
+    )
+}
+
+/// Opens the document, sends one request about it and exits properly.
+fn ask(text: &str, method: &str, params: &str) -> String {
+    let (exit_code, bodies) = serve(&[
+        request(1, "initialize", "{}"),
+        notification("initialized", "{}"),
+        did_open(text),
+        request(2, method, params),
+        request(3, "shutdown", "null"),
+        notification("exit", "null"),
+    ]);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(bodies.len(), 4);
+    bodies[2].clone()
+}
+
+const SRC: &str = "fun add(a, b) {\n    return a + b;\n}\nvar total = add(1, 2);\nprint total;";
+
+#[test]
+fn lifecycle() {
+    let (exit_code, bodies) = serve(&[
+        request(1, "initialize", "{}"),
+        request(2, "shutdown", "null"),
+        notification("exit", "null"),
+    ]);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert!(bodies[0].contains(r#""definitionProvider":true"#));
+    assert_eq!(bodies[1], r#"{"jsonrpc":"2.0","id":2,"result":null}"#);
+
+    // Exiting without shutdown, or when the input is closed.
+    let (exit_code, _) = serve(&[notification("exit", "null")]);
+    assert_eq!(exit_code, ExitCode::FAILURE);
+    assert_eq!(serve(&[]).0, ExitCode::FAILURE);
+}
+
+#[test]
+fn unknown_method() {
+    let (_, bodies) = serve(&[request(7, "textDocument/rename", "{}")]);
+    assert_eq!(
+        bodies[0],
+        r#"{"jsonrpc":"2.0","id":7,"error":{"code":-32601,"message":"Unknown method 'textDocument/rename'"}}"#
+    );
+}
+
+#[test]
+fn diagnostics() {
+    let (_, bodies) = serve(&[did_open("print 1;\nprint (2;\n")]);
+    assert_eq!(
+        bodies[0],
+        r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"file:///test.lox","diagnostics":[{"range":{"start":{"line":1,"character":8},"end":{"line":1,"character":9}},"severity":1,"source":"lox","message":"Error at end: Expected ')'."}]}}"#
+    );
+
+    // At the token, which is the character that does not scan.
+    let (_, bodies) = serve(&[did_open("print \"é\" + @;")]);
+    assert!(bodies[0].contains(
+        r#""range":{"start":{"line":0,"character":12},"end":{"line":0,"character":13}}"#
+    ));
+
+    let (_, bodies) = serve(&[did_open("print 1;")]);
+    assert!(bodies[0].ends_with(r#""diagnostics":[]}}"#));
+}
+
+#[test]
+fn definition() {
+    // `add` in `add(1, 2)`.
+    let response = ask(SRC, "textDocument/definition", &at(3, 13));
+    assert_eq!(
+        response,
+        r#"{"jsonrpc":"2.0","id":2,"result":{"uri":"file:///test.lox","range":{"start":{"line":0,"character":4},"end":{"line":0,"character":7}}}}"#
+    );
+
+    // `b` in `a + b`, which is a parameter.
+    let response = ask(SRC, "textDocument/definition", &at(1, 15));
+    assert!(response.contains(r#""start":{"line":0,"character":11}"#));
+}
+
+#[test]
+fn hover() {
+    let response = ask(SRC, "textDocument/hover", &at(3, 13));
+    assert!(response.contains(r#""value":"```lox\nfun add(a, b)\n```\nTakes 2 arguments.""#));
+
+    let src = "fun id(x) { return x; }\nfun zero() { return 0; }";
+    let response = ask(src, "textDocument/hover", &at(0, 5));
+    assert!(response.contains(r#"```\nTakes 1 argument.""#));
+    let response = ask(src, "textDocument/hover", &at(1, 5));
+    assert!(response.contains(r#"```\nTakes no arguments.""#));
+
+    let response = ask(SRC, "textDocument/hover", &at(4, 7));
+    assert!(response.contains(r#""value":"```lox\nvar total\n```""#));
+
+    let response = ask(SRC, "textDocument/hover", &at(4, 0));
+    assert!(response.ends_with(r#""result":null}"#));
+}
+
+#[test]
+fn document_symbols() {
+    let src = "fun outer() {\n    fun inner(x) {}\n}";
+    let response = ask(src, "textDocument/documentSymbol", &at(0, 0));
+    assert_eq!(
+        response,
+        r#"{"jsonrpc":"2.0","id":2,"result":[{"name":"outer","detail":"()","kind":12,"range":{"start":{"line":0,"character":0},"end":{"line":2,"character":1}},"selectionRange":{"start":{"line":0,"character":4},"end":{"line":0,"character":9}},"children":[{"name":"inner","detail":"(x)","kind":12,"range":{"start":{"line":1,"character":0},"end":{"line":1,"character":19}},"selectionRange":{"start":{"line":1,"character":8},"end":{"line":1,"character":13}},"children":[]}]}]}"#
+    );
+}
+
+#[test]
+fn document_symbols_in_control_flow() {
+    let src = "if (a) { fun then() {} } else fun other() {}\nwhile (b) { fun looped() {} }\nfor (;;) { fun counted() {} }";
+    let response = ask(src, "textDocument/documentSymbol", &at(0, 0));
+    let names: Vec<_> = response
+        .split(r#""name":""#)
+        .skip(1)
+        .map(|rest| rest.split('"').next().unwrap())
+        .collect();
+    assert_eq!(names, ["then", "other", "looped", "counted"]);
+}
+
+#[test]
+fn completion() {
+    let src =
+        "var global = 1;\nfun f(param) {\n    var local = 2;\n    \n}\n{\n    var hidden = 3;\n}";
+    let response = ask(src, "textDocument/completion", &at(3, 4));
+
+    for label in ["local", "param", "f", "global", "clock"] {
+        assert!(
+            response.contains(&format!(r#""label":"{label}""#)),
+            "{label}"
+        );
+    }
+    assert!(!response.contains(r#""label":"hidden""#));
+    // Locals come before globals.
+    assert!(response.find("local").unwrap() < response.find("global").unwrap());
+}
+
+#[test]
+fn completion_in_unfinished_code() {
+    let src = "var global = 1;\nfun f(param) {\n    var local = 2;\n    print lo";
+    // Before the rest of the document, and with the function left open.
+    for src in [&format!("{src}\n}}\nprint (;"), src] {
+        let response = ask(src, "textDocument/completion", &at(3, 12));
+        for label in ["local", "param", "f", "global"] {
+            assert!(
+                response.contains(&format!(r#""label":"{label}""#)),
+                "{label}"
+            );
+        }
+    }
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_parse_program_with, lox_parse_with, ParseOptions, RunOptions};
+
+const OPTIMIZE: ParseOptions = ParseOptions { optimize: true };
+
+fn parse_program(src: &str) -> String {
+    let (exit_code, output, _) =
+        common::capture(|ok_buf, err_buf| lox_parse_program_with(src, &OPTIMIZE, ok_buf, err_buf));
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    output
+}
+
+/// Runs the source with and without `-O`, and expects the same result.
+fn run(src: &str) -> (ExitCode, String, String) {
+    let results: Vec<_> = [false, true]
+        .into_iter()
+        .map(|optimize| {
+            let options = RunOptions {
+                optimize,
+                ..Default::default()
+            };
+            common::run_with(src, &options)
+        })
+        .collect();
+    assert_eq!(results[0], results[1]);
+    results[0].clone()
+}
+
+#[test]
+fn folds_constants() {
+    let result = common::capture(|ok_buf, err_buf| {
+        lox_parse_with("(1 + 2) * -3 == -9", &OPTIMIZE, ok_buf, err_buf)
+    });
+    assert_eq!(result, (ExitCode::SUCCESS, "true\n".into(), "".into()));
+
+    let src = r#"
+        print "a" + "b";
+        print !nil;
+        print false or x;
+        print (x);
+    "#;
+    assert_eq!(
+        parse_program(src),
+        "(print ab)\n(print true)\n(print x)\n(print x)\n"
+    );
+}
+
+#[test]
+fn removes_dead_branches() {
+    let src = r#"
+        if (true) print "yes"; else print "no";
+        if (false) print "gone";
+        while (false) print "never";
+        while (1 > 2) {}
+        if (x) if (false) print "gone";
+    "#;
+    assert_eq!(parse_program(src), "(print yes)\n(if x (block))\n");
+}
+
+#[test]
+fn runs_the_same() {
+    let src = r#"
+        var n = 0;
+        while (n < 3 and true) n = n + 1;
+        if (2 * 3 > 5) print n; else print "no";
+        print "a" + "b" == "ab";
+    "#;
+    let (exit_code, output, _) = run(src);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(output, "3\ntrue\n");
+}
+
+#[test]
+fn keeps_runtime_errors() {
+    let src = "print 1 + 2;\nprint (1 + 2) -\n  \"a\";";
+    let (exit_code, output, error) = run(src);
+    assert_eq!(exit_code, ExitCode::from(70));
+    assert_eq!(output, "3\n");
+    assert_eq!(error, "[line 3] Error: Operand must be number\n");
+}
@@ -1,6 +1,6 @@
 use std::process::ExitCode;
 
-use codecrafters_interpreter::lox_parse;
+use codecrafters_interpreter::{lox_parse, lox_parse_program};
 
 macro_rules! parse_test {
     ($src:expr, exit_code = $exit_code:expr, stdout = $stdout:expr, stderr = $stderr:expr) => {
@@ -245,3 +245,81 @@ fn wz8() {
         stderr = "[line 1] Error at '+': Expected expression."
     );
 }
+
+/// 'parse' prints calls, fields and variables as it did before '--program'.
+#[test]
+fn calls_and_fields() {
+    parse_test!(
+        "f(a, b).c",
+        exit_code = 0,
+        stdout = "IDENTIFIER f null(IDENTIFIER a null, IDENTIFIER b null).c",
+        stderr = ""
+    );
+}
+
+/// Parses the whole program, and returns the exit code, stdout and stderr.
+fn parse_program(src: &str) -> (ExitCode, String, String) {
+    let (mut ok_buf, mut err_buf) = (Vec::new(), Vec::new());
+    let exit_code = lox_parse_program(src, &mut ok_buf, &mut err_buf);
+    (
+        exit_code,
+        String::from_utf8(ok_buf).unwrap(),
+        String::from_utf8(err_buf).unwrap(),
+    )
+}
+
+#[test]
+fn program() {
+    let (exit_code, stdout, _) = parse_program(include_str!("test_suites/recursive.lox"));
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(
+        stdout,
+        "(fun fibonacci (n) (block (if (== n 0.0) (return 0.0)) (if (== n 1.0) (return 1.0)) \
+         (return (+ (call fibonacci (- n 1.0)) (call fibonacci (- n 2.0))))))
+(print (call fibonacci 10.0))
+"
+    );
+
+    let src =
+        "var i;\nfor (;;) {}\nwhile (i < 3) i = i + 1;\nif (a) b(1, i); else { c.d; return; }";
+    let (_, stdout, _) = parse_program(src);
+    assert_eq!(
+        stdout,
+        "(var i)
+(for _ _ _ (block))
+(while (< i 3.0) (expr (= i (+ i 1.0))))
+(if a (expr (call b 1.0 i)) (block (expr (. c d)) (return)))
+"
+    );
+}
+
+#[test]
+fn program_errors() {
+    let (exit_code, stdout, stderr) = parse_program("print 1;\nprint 2");
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, "[line 2] Expected semicolon, but found \n");
+}
+
+/// Tokens are pulled by the parser as it goes, but errors of tokenizing are
+/// still reported first, even where the parser stops before them.
+#[test]
+fn tokenize_errors_first() {
+    parse_test!(
+        "1 2 @",
+        exit_code = 65,
+        stdout = "",
+        stderr = "[line 1] Error: Unexpected character: @"
+    );
+
+    parse_test!(
+        "(1\n\"open",
+        exit_code = 65,
+        stdout = "",
+        stderr = "[line 2] Error: Unterminated string."
+    );
+
+    let (exit_code, _, stderr) = parse_program("print (1;\nvar a = 1;\n@");
+    assert_eq!(exit_code, ExitCode::from(65));
+    assert_eq!(stderr, "[line 3] Error: Unexpected character: @\n");
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_profile, RunOptions};
+
+const SRC: &str = "fun countdown(n) {
+    if (n > 0) countdown(n - 1);
+}
+countdown(3);
+print len(\"ab\") * len(\"c\");";
+
+/// Profiles the source, and returns what is printed, the table and the
+/// folded stacks without their times.
+fn profile(src: &str) -> (String, Vec<String>, Vec<String>) {
+    let (mut report, mut folded) = (Vec::new(), Vec::new());
+    let (exit_code, output, error) = common::capture(|ok_buf, err_buf| {
+        lox_profile(
+            src,
+            &RunOptions::default(),
+            ok_buf,
+            err_buf,
+            &mut report,
+            Some(&mut folded),
+        )
+    });
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert!(error.is_empty());
+
+    let table = String::from_utf8(report)
+        .unwrap()
+        .lines()
+        .map(|row| row.split_whitespace().take(2).collect::<Vec<_>>().join(" "))
+        .collect();
+    let stacks = String::from_utf8(folded)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (stack, micros) = line.rsplit_once(' ').unwrap();
+            micros.parse::<u128>().unwrap();
+            stack.to_string()
+        })
+        .collect();
+    (output, table, stacks)
+}
+
+#[test]
+fn call_counts() {
+    let (output, mut table, _) = profile(SRC);
+    assert_eq!(output, "2\n");
+    assert_eq!(table.remove(0), "Function Calls");
+
+    // The order of the rows depends on the timings.
+    table.sort();
+    assert_eq!(table, ["countdown:1 4", "len 2"]);
+}
+
+#[test]
+fn folded_stacks() {
+    let (_, _, stacks) = profile(SRC);
+    assert_eq!(
+        stacks,
+        [
+            "<script>",
+            "<script>;countdown:1",
+            "<script>;countdown:1;countdown:1",
+            "<script>;countdown:1;countdown:1;countdown:1",
+            "<script>;countdown:1;countdown:1;countdown:1;countdown:1",
+            "<script>;len",
+        ]
+    );
+}
+
+#[test]
+fn functions_of_the_same_name() {
+    // Told apart by the line they are defined at.
+    let src = "fun f() {}\nf();\n{\n    fun f() {}\n    f();\n    f();\n}";
+    let (_, mut table, _) = profile(src);
+    table.sort();
+    assert_eq!(table, ["Function Calls", "f:1 1", "f:4 2"]);
+}
+
+#[test]
+fn errors_are_profiled() {
+    let (mut ok_buf, mut err_buf, mut report) = (Vec::new(), Vec::new(), Vec::new());
+    let src = "fun fail() {\n    return -\"a\";\n}\nfail();";
+    let exit_code = lox_profile(
+        src,
+        &RunOptions::default(),
+        &mut ok_buf,
+        &mut err_buf,
+        &mut report,
+        None,
+    );
+    assert_eq!(exit_code, ExitCode::from(70));
+    assert!(String::from_utf8(report)
+        .unwrap()
+        .lines()
+        .any(|row| row.starts_with("fail:1 ")));
+}
@@ -0,0 +1,102 @@
+#[macro_use]
+mod common;
+
+use codecrafters_interpreter::{Capabilities, Capability, RunOptions};
+use common::run_ok;
+
+fn seeded(seed: u64) -> RunOptions {
+    RunOptions {
+        seed: Some(seed),
+        ..Default::default()
+    }
+}
+
+const ROLLS: &str = r#"
+    var l = list();
+    for (var i = 0; i < 10; i = i + 1) push(l, i);
+    print shuffle(l);
+    print random_int(1, 6);
+    print random();
+"#;
+
+#[test]
+fn same_seed_same_values() {
+    assert_eq!(run_ok(ROLLS, &seeded(42)).0, run_ok(ROLLS, &seeded(42)).0);
+    assert_ne!(run_ok(ROLLS, &seeded(42)).0, run_ok(ROLLS, &seeded(43)).0);
+}
+
+#[test]
+fn values_in_range() {
+    let src = r#"
+        var ok = true;
+        for (var i = 0; i < 1000; i = i + 1) {
+            var r = random();
+            var n = random_int(-2, 2);
+            if (r < 0 or r >= 1) ok = false;
+            if (n < -2 or n > 2) ok = false;
+        }
+        print ok;
+        print random_int(7, 7);
+    "#;
+    assert_eq!(run_ok(src, &seeded(0)).0, "true\n7\n");
+}
+
+#[test]
+fn shuffle_is_a_permutation() {
+    let src = r#"
+        var l = list();
+        for (var i = 0; i < 20; i = i + 1) push(l, i);
+        shuffle(l);
+        var sum = 0;
+        for (var i = 0; i < len(l); i = i + 1) sum = sum + get(l, i);
+        print len(l);
+        print sum;
+    "#;
+    assert_eq!(run_ok(src, &seeded(7)).0, "20\n190\n");
+}
+
+#[test]
+fn invalid_range() {
+    run_test!(
+        "random_int(3, 1);",
+        seeded(0),
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Operand must be two integers, lo <= hi"
+    );
+}
+
+#[test]
+fn too_wide_range() {
+    for src in [
+        "random_int(0, 100000000000000000000);",
+        "random_int(-9007199254740992, 9007199254740992);",
+    ] {
+        run_test!(
+            src,
+            seeded(0),
+            exit_code = 70,
+            stdout = "",
+            stderr = "[line 1] Error: Operand must be integers at most 2^53 apart"
+        );
+    }
+
+    let src = "var n = random_int(0, 9007199254740992); print n >= 0 and n <= 9007199254740992;";
+    assert_eq!(run_ok(src, &seeded(0)).0, "true\n");
+}
+
+#[test]
+fn random_can_be_denied() {
+    let options = RunOptions {
+        capabilities: Capabilities::none().with(Capability::Pure),
+        ..seeded(0)
+    };
+    run_test!(
+        "random();",
+        options,
+        exit_code = 70,
+        stdout = "",
+        stderr =
+            "[line 1] Error: Undefined variable 'random'. It requires the 'random' capability."
+    );
+}
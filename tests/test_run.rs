@@ -33,3 +33,36 @@ fn nested_func() {
 fn block_scope_resolution() {
     test_source_run!("block_scope_resolution", "global\nglobal");
 }
+
+#[test]
+fn closure_counter() {
+    test_source_run!("closure_counter", "2\n1");
+}
+
+#[test]
+fn shadowing() {
+    test_source_run!("shadowing", "2\nglobal shadowed\nafter\nglobal");
+}
+
+#[test]
+fn function_identity() {
+    test_source_run!("function_identity", "true\nfalse\ntrue");
+}
+
+#[test]
+fn strings() {
+    test_source_run!("strings", "hello\nhello!\ntrue\ntrue");
+}
+
+#[test]
+fn operands_once() {
+    test_source_run!("operands_once", "2\naa\n4");
+}
+
+#[test]
+fn truthiness() {
+    test_source_run!(
+        "truthiness",
+        "function\nnative\nlist\nmap\nfalse\nand\nnil is falsy\nzero\nempty string"
+    );
+}
@@ -0,0 +1,116 @@
+#[macro_use]
+mod common;
+
+use codecrafters_interpreter::{Capabilities, Capability, RunOptions};
+
+fn with_args(args: &[&str]) -> RunOptions {
+    RunOptions {
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn args_list() {
+    run_test!(
+        r#"
+        print len(args);
+        for (var i = 0; i < len(args); i = i + 1) {
+            print get(args, i);
+        }
+        print args;
+        "#,
+        with_args(&["input.txt", "--verbose"]),
+        exit_code = 0,
+        stdout = "2\ninput.txt\n--verbose\n[input.txt, --verbose]",
+        stderr = ""
+    );
+
+    run_test!(
+        "print len(args);",
+        RunOptions::default(),
+        exit_code = 0,
+        stdout = "0",
+        stderr = ""
+    );
+}
+
+#[test]
+fn list_natives() {
+    run_test!(
+        r#"
+        var l = list();
+        push(push(l, 1), "two");
+        print l;
+        print l == l;
+        print l == list();
+        if (list()) print "lists are truthy";
+        "#,
+        RunOptions::default(),
+        exit_code = 0,
+        stdout = "[1, two]\ntrue\nfalse\nlists are truthy",
+        stderr = ""
+    );
+
+    run_test!(
+        "get(args, 0);",
+        RunOptions::default(),
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Index 0 is out of range for a list of length 0."
+    );
+}
+
+#[test]
+fn getenv() {
+    let options = RunOptions {
+        capabilities: Capabilities::default().with(Capability::Env),
+        ..Default::default()
+    };
+
+    run_test!(
+        r#"print getenv("CARGO_PKG_NAME"); print getenv("LOX_SURELY_NOT_SET");"#,
+        options,
+        exit_code = 0,
+        stdout = "codecrafters-interpreter\nnil",
+        stderr = ""
+    );
+}
+
+#[test]
+fn exit_with_code() {
+    let options = RunOptions {
+        capabilities: Capabilities::default().with(Capability::Process),
+        ..Default::default()
+    };
+
+    run_test!(
+        r#"
+        fun check(n) {
+            if (n > 2) exit(3);
+            print n;
+        }
+        for (var i = 0; i < 10; i = i + 1) check(i);
+        "#,
+        options,
+        exit_code = 3,
+        stdout = "0\n1\n2",
+        stderr = ""
+    );
+
+    run_test!(
+        "exit(0); print 1;",
+        options,
+        exit_code = 0,
+        stdout = "",
+        stderr = ""
+    );
+
+    run_test!(
+        "exit(256);",
+        options,
+        exit_code = 70,
+        stdout = "",
+        stderr = "[line 1] Error: Operand must be an integer between 0 and 255"
+    );
+}
@@ -867,3 +867,45 @@ RIGHT_BRACE } null
 EOF  null"#
     );
 }
+
+/// Shebang line is skipped, but still counted as a line.
+#[test]
+fn shebang() {
+    tokenize_test! {
+        "#!/usr/bin/env lox\nprint @;",
+        exit_code = 65,
+        stdout = "PRINT print null\nSEMICOLON ; null\nEOF  null",
+        stderr = "[line 2] Error: Unexpected character: @"
+    };
+
+    // Only at the very start of the source.
+    tokenize_test! {
+        " #!",
+        exit_code = 65,
+        stdout = "BANG ! null\nEOF  null",
+        stderr = "[line 1] Error: Unexpected character: #"
+    };
+}
+
+/// Whitespaces and comments are skipped in a loop, so there is no limit on
+/// how many there are in a row.
+#[test]
+fn many_comment_lines() {
+    let src = format!("{}print 1;", "// comment\n    \n".repeat(200_000));
+    tokenize_test! {
+        &src,
+        stdout = "PRINT print null\nNUMBER 1 1.0\nSEMICOLON ; null\nEOF  null"
+    };
+}
+
+/// Only ASCII digits and letters make numbers and identifiers, but any
+/// whitespace separates tokens.
+#[test]
+fn non_ascii() {
+    tokenize_test! {
+        "x\u{3000}1.\u{661}",
+        exit_code = 65,
+        stdout = "IDENTIFIER x null\nNUMBER 1 1.0\nDOT . null\nEOF  null",
+        stderr = "[line 1] Error: Unexpected character: \u{661}"
+    };
+}
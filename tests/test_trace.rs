@@ -0,0 +1,121 @@
+mod common;
+
+use std::process::ExitCode;
+
+use codecrafters_interpreter::{lox_run_instrumented, lox_trace, Instruments, RunOptions};
+
+/// Runs the source, and returns the exit code, what is printed and the
+/// trace.
+fn run_traced(src: &str, filter: Option<&str>) -> (ExitCode, String, String) {
+    let mut trace = Vec::new();
+    let (exit_code, output, _) = common::capture(|ok_buf, err_buf| {
+        lox_trace(
+            src,
+            &RunOptions::default(),
+            filter,
+            ok_buf,
+            err_buf,
+            &mut trace,
+        )
+    });
+    (exit_code, output, String::from_utf8(trace).unwrap())
+}
+
+const SRC: &str = "fun add(a, b) {
+    var sum = a + b;
+    return sum;
+}
+fun twice(s) {
+    return add(len(s), len(s));
+}
+var total = 0;
+total = twice(\"ab\");
+print total;";
+
+#[test]
+fn statements_calls_and_assignments() {
+    let (exit_code, output, trace) = run_traced(SRC, None);
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(output, "4\n");
+    assert_eq!(
+        trace,
+        r#"[line 1] fun add(a, b) {
+[line 5] fun twice(s) {
+[line 8] var total = 0;
+[line 8] total = 0
+[line 9] total = twice("ab");
+call twice("ab")
+  [line 6] return add(len(s), len(s));
+  call len("ab")
+  len returned 2
+  call len("ab")
+  len returned 2
+  call add(2, 2)
+    [line 2] var sum = a + b;
+    [line 2] sum = 4
+    [line 3] return sum;
+  add returned 4
+twice returned 4
+[line 9] total = 4
+[line 10] print total;
+"#
+    );
+}
+
+#[test]
+fn filter_by_function() {
+    let (_, output, trace) = run_traced(SRC, Some("add"));
+    assert_eq!(output, "4\n");
+    assert_eq!(
+        trace,
+        "  call add(2, 2)
+    [line 2] var sum = a + b;
+    [line 2] sum = 4
+    [line 3] return sum;
+  add returned 4
+"
+    );
+
+    let (_, _, trace) = run_traced(SRC, Some("missing"));
+    assert!(trace.is_empty());
+}
+
+#[test]
+fn failed_calls() {
+    let src = "fun fail(x) {\n    return -x;\n}\nfail(\"a\");";
+    let (exit_code, _, trace) = run_traced(src, None);
+    assert_eq!(exit_code, ExitCode::from(70));
+    assert!(trace.ends_with("call fail(\"a\")\n  [line 2] return -x;\nfail failed\n"));
+}
+
+#[test]
+fn with_profile_and_coverage() {
+    let (mut ok_buf, mut err_buf) = (Vec::new(), Vec::new());
+    let (mut trace, mut report, mut lcov) = (Vec::new(), Vec::new(), Vec::new());
+    let instruments = Instruments {
+        profile: Some(&mut report),
+        coverage: Some(("add.lox", &mut lcov)),
+        trace: Some(&mut trace),
+        ..Default::default()
+    };
+    let exit_code = lox_run_instrumented(
+        SRC,
+        &RunOptions::default(),
+        instruments,
+        &mut ok_buf,
+        &mut err_buf,
+    );
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    assert_eq!(String::from_utf8(ok_buf).unwrap(), "4\n");
+
+    // Each is the same as without the others.
+    assert_eq!(String::from_utf8(trace).unwrap(), run_traced(SRC, None).2);
+    let report = String::from_utf8(report).unwrap();
+    assert!(
+        report.lines().any(|row| row.starts_with("add:1 ")),
+        "{report}"
+    );
+    let lcov = String::from_utf8(lcov).unwrap();
+    assert!(lcov.starts_with("TN:\nSF:add.lox\n"), "{lcov}");
+    assert!(lcov.contains("DA:2,1\n"), "{lcov}");
+}